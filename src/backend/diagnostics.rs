@@ -0,0 +1,21 @@
+//! Problems found while rendering
+//!
+//! Rendering can't fail, so widgets fall back to defaults and record what went wrong here. The
+//! renderer shows everything recorded during a frame on top of it.
+
+use std::collections::BTreeSet;
+
+use parking_lot::{Mutex, const_mutex};
+
+static DIAGNOSTICS: Mutex<BTreeSet<String>> = const_mutex(BTreeSet::new());
+
+pub fn record<S: Into<String>>(msg: S) {
+    DIAGNOSTICS.lock().insert(msg.into());
+}
+
+/// Everything recorded since the last call
+pub fn take() -> Vec<String> {
+    std::mem::take(&mut *DIAGNOSTICS.lock())
+        .into_iter()
+        .collect()
+}
@@ -0,0 +1,147 @@
+//! Filters used by templates, e.g. `{{mem_used | bytes}}` or `{{title | truncate(20)}}`
+
+use color_eyre::eyre::{Error, Result};
+
+type FilterFn = fn(&str, &[String]) -> Result<String>;
+
+/// Every built-in filter, looked up by name
+const FILTERS: &[(&str, FilterFn)] = &[
+    ("upper", upper),
+    ("lower", lower),
+    ("trim", trim),
+    ("round", round),
+    ("bytes", bytes),
+    ("truncate", truncate),
+    ("pad", pad),
+];
+
+pub fn apply(name: &str, value: &str, args: &[String]) -> Result<String> {
+    let Some((_, filter)) = FILTERS.iter().find(|(n, _)| *n == name) else {
+        return Err(Error::msg(format!("Unknown filter \"{name}\"")));
+    };
+
+    filter(value, args)
+}
+
+/// Read argument `i` as `T`, or `default` if it was not given
+fn arg<T: std::str::FromStr>(args: &[String], i: usize, default: T) -> Result<T> {
+    match args.get(i) {
+        Some(a) => a
+            .parse()
+            .map_err(|_| Error::msg(format!("Invalid filter argument \"{a}\""))),
+        None => Ok(default),
+    }
+}
+
+fn number(value: &str) -> Result<f64> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| Error::msg(format!("\"{value}\" is not a number")))
+}
+
+fn upper(value: &str, _: &[String]) -> Result<String> {
+    Ok(value.to_uppercase())
+}
+
+fn lower(value: &str, _: &[String]) -> Result<String> {
+    Ok(value.to_lowercase())
+}
+
+fn trim(value: &str, _: &[String]) -> Result<String> {
+    Ok(value.trim().to_string())
+}
+
+/// `round(n)`: round to `n` decimals, defaults to 0
+fn round(value: &str, args: &[String]) -> Result<String> {
+    // the formatter takes at most a u16
+    let decimals = arg::<u16>(args, 0, 0)? as usize;
+    Ok(format!("{:.decimals$}", number(value)?))
+}
+
+/// Human readable size from a number of bytes, e.g. `1.5 GiB`
+fn bytes(value: &str, _: &[String]) -> Result<String> {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+
+    let mut n = number(value)?;
+    let mut unit = 0;
+    while n.abs() >= 1024.0 && unit < UNITS.len() - 1 {
+        n /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        Ok(format!("{n:.0} {}", UNITS[unit]))
+    } else {
+        Ok(format!("{n:.1} {}", UNITS[unit]))
+    }
+}
+
+/// `truncate(n, ellipsis)`: cut to at most `n` characters, ending with `ellipsis` (defaults to `…`)
+fn truncate(value: &str, args: &[String]) -> Result<String> {
+    let len: usize = arg(args, 0, usize::MAX)?;
+    let ellipsis = args.get(1).map(|e| e.as_str()).unwrap_or("…");
+
+    if value.chars().count() <= len {
+        return Ok(value.to_string());
+    }
+
+    let keep = len.saturating_sub(ellipsis.chars().count());
+    Ok(value
+        .chars()
+        .take(keep)
+        .chain(ellipsis.chars())
+        .take(len)
+        .collect())
+}
+
+/// `pad(n)`: right align to a width of `n` characters
+fn pad(value: &str, args: &[String]) -> Result<String> {
+    // the formatter takes at most a u16
+    let width = arg::<u16>(args, 0, 0)? as usize;
+    Ok(format!("{value:>width$}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(name: &str, value: &str, args: &[&str]) -> Result<String> {
+        let args: Vec<String> = args.iter().map(|a| a.to_string()).collect();
+        apply(name, value, &args)
+    }
+
+    #[test]
+    fn text() {
+        assert_eq!(run("upper", "aB", &[]).unwrap(), "AB");
+        assert_eq!(run("lower", "aB", &[]).unwrap(), "ab");
+        assert_eq!(run("trim", " a ", &[]).unwrap(), "a");
+        assert_eq!(run("pad", "7", &["3"]).unwrap(), "  7");
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(run("round", "1.26", &[]).unwrap(), "1");
+        assert_eq!(run("round", "1.26", &["1"]).unwrap(), "1.3");
+        assert_eq!(run("bytes", "512", &[]).unwrap(), "512 B");
+        assert_eq!(run("bytes", "1610612736", &[]).unwrap(), "1.5 GiB");
+        assert!(run("round", "abc", &[]).is_err());
+    }
+
+    #[test]
+    fn truncate() {
+        assert_eq!(run("truncate", "abcdef", &["3"]).unwrap(), "ab…");
+        assert_eq!(run("truncate", "abcdef", &["4", ".."]).unwrap(), "ab..");
+        assert_eq!(run("truncate", "abc", &["3"]).unwrap(), "abc");
+        assert_eq!(run("truncate", "abcdef", &["1", "..."]).unwrap(), ".");
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(run("nope", "a", &[]).is_err());
+        assert!(run("pad", "a", &["x"]).is_err());
+        // too wide for the formatter
+        assert!(run("pad", "a", &["70000"]).is_err());
+        assert!(run("round", "1", &["70000"]).is_err());
+    }
+}
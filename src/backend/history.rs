@@ -0,0 +1,69 @@
+//! Rolling history of numeric store values, for widgets that show trends
+
+use std::collections::{BTreeMap, VecDeque};
+
+use crate::backend::{
+    History,
+    value::{Value, lookup},
+};
+
+/// Keeps the last `capacity` values pushed to it
+#[derive(Clone, Debug)]
+pub struct RingBuffer<T> {
+    buf: VecDeque<T>,
+    capacity: usize,
+}
+
+impl<T> RingBuffer<T> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            buf: VecDeque::with_capacity(capacity),
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Add a value, dropping the oldest one when full
+    pub fn push(&mut self, value: T) {
+        if self.buf.len() == self.capacity {
+            self.buf.pop_front();
+        }
+        self.buf.push_back(value);
+    }
+
+    /// Oldest value first
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        self.buf.iter()
+    }
+
+    /// Make room for at least `capacity` values, never shrinks
+    pub fn reserve(&mut self, capacity: usize) {
+        self.capacity = self.capacity.max(capacity);
+    }
+}
+
+/// Start recording `key`, keeping at least `samples` values
+///
+/// Widgets watching the same key share one buffer
+pub fn watch(history: &History, key: &str, samples: usize) {
+    history
+        .write()
+        .entry(key.to_string())
+        .and_modify(|r| r.reserve(samples))
+        .or_insert_with(|| RingBuffer::new(samples));
+}
+
+/// Push the current value of every watched key, returns true if anything was recorded
+///
+/// Keys that are missing or not a number are skipped
+pub fn record(history: &History, store: &BTreeMap<String, Value>) -> bool {
+    let mut recorded = false;
+
+    for (key, ring) in history.write().iter_mut() {
+        if let Some(v) = lookup(store, key).and_then(|v| v.as_number()) {
+            ring.push(v);
+            recorded = true;
+        }
+    }
+
+    recorded
+}
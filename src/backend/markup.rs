@@ -0,0 +1,49 @@
+//! Inline styling inside `<text>`, e.g. `CPU <b>{{cpu}}</b> <span fg="red">hot</span>`
+
+use ratatui::style::{Modifier, Style};
+
+use crate::{
+    backend::{Attribute, theme::ThemeColor},
+    utils::parse_attr_or_record,
+};
+
+/// A run of text with the styling of every markup element around it
+#[derive(Clone, Debug)]
+pub struct Segment {
+    pub text: Attribute,
+    pub style: SegmentStyle,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SegmentStyle {
+    pub modifiers: Modifier,
+    /// From the closest `<span>` that sets it
+    pub fg: Option<Attribute>,
+    pub bg: Option<Attribute>,
+}
+
+impl SegmentStyle {
+    /// Colours are read on every render, as they may be templated
+    pub fn resolve(&self) -> Style {
+        let mut style = Style::new().add_modifier(self.modifiers);
+        if let Some(fg) = parse_attr_or_record::<ThemeColor>("span", "fg", self.fg.as_ref()) {
+            style = style.fg(fg.into());
+        }
+        if let Some(bg) = parse_attr_or_record::<ThemeColor>("span", "bg", self.bg.as_ref()) {
+            style = style.bg(bg.into());
+        }
+        style
+    }
+}
+
+/// Modifier added by a markup element, `<span>` only sets colours
+pub fn modifier(tag: &str) -> Modifier {
+    match tag {
+        "b" => Modifier::BOLD,
+        "i" => Modifier::ITALIC,
+        "u" => Modifier::UNDERLINED,
+        "dim" => Modifier::DIM,
+        "reversed" => Modifier::REVERSED,
+        _ => Modifier::empty(),
+    }
+}
@@ -1,16 +1,45 @@
+pub mod diagnostics;
+mod filters;
+mod history;
+mod markup;
 mod modules;
+mod plugins;
+pub mod scheduler;
+pub mod schema;
+pub mod stylesheet;
+pub mod template;
+pub mod theme;
+pub mod validator;
+pub mod value;
 pub mod xmlparser;
 
-use color_eyre::eyre::{Error, Result};
+use color_eyre::eyre::Result;
 use parking_lot::RwLock;
 use ratatui::{
     layout::{Constraint, Direction},
     widgets::WidgetRef,
 };
 
-use std::{cell::RefCell, collections::BTreeMap, fmt, ops::Range, rc::Rc, sync::Arc};
+use std::{
+    any::Any,
+    cell::RefCell,
+    collections::BTreeMap,
+    fmt,
+    rc::Rc,
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+use history::RingBuffer;
+use template::{MissingKeys, Template, split_template};
+use value::Value;
 
-pub type Store = Arc<RwLock<BTreeMap<String, String>>>;
+pub type Store = Arc<RwLock<BTreeMap<String, Value>>>;
+/// Recent values of the store keys some widget asked to keep, see `history::watch`
+pub type History = Arc<RwLock<BTreeMap<String, RingBuffer<f64>>>>;
 pub type Attributes = Arc<RwLock<BTreeMap<String, Attribute>>>;
 pub type RTRef = Rc<RefCell<RenderTree>>;
 pub type RenderCallback = Box<dyn WidgetRef>;
@@ -24,74 +53,166 @@ pub struct Attribute {
 
 #[derive(Clone, Debug)]
 pub struct AttrDerive {
-    derive_from: String,
+    template: Template,
     store: Option<Store>,
-    template_at: Range<usize>,
+    missing: MissingKeys,
 }
 
 impl Attribute {
-    pub fn create(value: String, store: Option<Store>) -> Attribute {
-        let mut derive: Option<AttrDerive> = None;
-        if let Some(derive_start) = value.find("{{")
-            && let Some(derive_end) = value.get(derive_start..).and_then(|f| f.find("}}"))
-        {
-            if derive_start == 0 // cannot be escaped
-                // if it is None or escaped we don't want to template
-                || value.get(derive_start - 1..derive_start).map(|f| f != "\\") == Some(true)
-            {
-                let template_at = derive_start..derive_end + 1;
-                let derive_from = value.get(derive_start + 2..derive_end).unwrap().to_string(); // should be safe now;
-                derive = Some(AttrDerive {
-                    derive_from,
-                    template_at,
-                    store
-                })
-            }
+    pub fn create(value: String, store: Option<Store>, missing: &MissingKeys) -> Attribute {
+        let template = Template::parse(&value);
+
+        if !template.has_placeholders() {
+            // only escapes to resolve, which can't fail without placeholders
+            let value = template.render(&BTreeMap::new(), missing).unwrap_or(value);
+            return Self {
+                value,
+                derive: None,
+            };
         }
 
         Self {
             value,
-            derive,
+            derive: Some(AttrDerive {
+                template,
+                store,
+                missing: missing.clone(),
+            }),
         }
     }
 
     pub fn read(&self) -> Result<String> {
-        let mut value = self.value.clone();
-
-        if let Some(derive) = &self.derive && let Some(s) = &derive.store {
-            let lock = s.read();
-            let store_val = lock.get(&derive.derive_from);
-
-            if store_val.is_none() {
-                return Err(Error::msg(format!(
-                    "Key \"{}\" does not exist in plugin store",
-                    &derive.derive_from
-                )));
-            }
+        let Some(derive) = &self.derive else {
+            return Ok(self.value.clone());
+        };
 
-            value.replace_range(derive.template_at.clone(), store_val.unwrap());
+        match &derive.store {
+            Some(s) => derive.template.render(&s.read(), &derive.missing),
+            // outside of plugins every key is missing
+            None => derive.template.render(&BTreeMap::new(), &derive.missing),
         }
+    }
 
-        Ok(value)
+    /// Split into several attributes on `sep`, placeholders are never split
+    ///
+    /// Used for attributes that hold a list, such as several titles
+    pub fn split(&self, sep: char) -> Vec<Attribute> {
+        match &self.derive {
+            Some(derive) => split_template(&self.value, sep)
+                .into_iter()
+                .map(|v| Attribute::create(v.to_string(), derive.store.clone(), &derive.missing))
+                .collect(),
+            None => self
+                .value
+                .split(sep)
+                .map(|v| Attribute {
+                    value: v.to_string(),
+                    derive: None,
+                })
+                .collect(),
+        }
     }
 }
 
 pub trait Module {
-    fn subroutine(routine: &mut SubRoutine) {}
+    fn subroutine(_routine: &mut SubRoutine) {}
 }
 
+/// Collects data for a plugin and writes it into the plugin's store
+///
+/// Subroutines are driven by the `Scheduler`, which calls `tick` once every `interval`
 pub struct SubRoutine {
-    store: Store,
-    attributes: Store,
+    pub store: Store,
+    pub history: History,
+    pub attributes: Attributes,
+    pub interval: Duration,
+    /// Tick on multiples of `interval` on the wall clock
+    pub align: bool,
+    pub state: RoutineState,
+    /// Set once the scheduler is dropped, long running routines should give up
+    stop: Arc<AtomicBool>,
     routine: fn(&mut Self),
 }
 
+/// Data a routine keeps between ticks, e.g. counters to compute rates from
+#[derive(Default)]
+pub struct RoutineState(Option<Box<dyn Any + Send>>);
+
+impl RoutineState {
+    /// Get the state, created with `Default` on first use
+    pub fn get<T: Default + Send + 'static>(&mut self) -> &mut T {
+        if !self.0.as_ref().is_some_and(|s| s.is::<T>()) {
+            self.0 = Some(Box::new(T::default()));
+        }
+
+        // just made sure it is a T
+        self.0.as_mut().and_then(|s| s.downcast_mut()).unwrap()
+    }
+}
+
+impl SubRoutine {
+    pub fn new(
+        store: Store,
+        history: History,
+        attributes: Attributes,
+        interval: Duration,
+        align: bool,
+        routine: fn(&mut Self),
+    ) -> Self {
+        Self {
+            store,
+            history,
+            attributes,
+            interval,
+            align,
+            state: RoutineState::default(),
+            stop: Arc::default(),
+            routine,
+        }
+    }
+
+    /// True once the routine is no longer wanted, e.g. after the layout was reloaded
+    pub fn stopped(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+
+    /// Flag the scheduler sets to stop the routine
+    pub fn stop_flag(&self) -> Arc<AtomicBool> {
+        self.stop.clone()
+    }
+
+    /// Write every value into the store, replacing older values of the same keys
+    pub fn publish<V: Into<Value>>(&self, values: impl IntoIterator<Item = (String, V)>) {
+        self.store
+            .write()
+            .extend(values.into_iter().map(|(k, v)| (k, v.into())));
+    }
+
+    /// Replace the whole store with `values`, keys that are left out are removed
+    pub fn replace<V: Into<Value>>(&self, values: impl IntoIterator<Item = (String, V)>) {
+        *self.store.write() = values.into_iter().map(|(k, v)| (k, v.into())).collect();
+    }
+
+    /// Run the routine once, returns true if the store or its history was changed
+    pub fn tick(&mut self) -> bool {
+        let before = self.store.read().clone();
+        (self.routine)(self);
+
+        let store = self.store.read();
+        let recorded = history::record(&self.history, &store);
+        recorded || *store != before
+    }
+}
+
 pub struct RenderTree {
     pub children: Vec<RTRef>,
     pub store: Option<Store>,
+    pub history: Option<History>,
     pub attributes: Attributes,
     pub size_constraint: Constraint,
     pub ctype: ComponentType,
+    /// Tag as written, unknown plugins all share `ComponentType::Plugin`
+    pub tag: String,
     pub renderer: RenderCallback,
 }
 
@@ -99,6 +220,7 @@ impl fmt::Debug for RenderTree {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> fmt::Result {
         f.debug_struct("RenderTree")
             .field("type", &self.ctype)
+            .field("tag", &self.tag)
             .field("size", &self.size_constraint)
             .field("attributes", &self.attributes)
             .field("children", &self.children)
@@ -114,6 +236,17 @@ pub enum ComponentType {
     Window,
     Text,
     Block,
+    Gauge,
+    Sparkline,
+    Chart,
+    BarChart,
+    Table,
+    List,
+    Exec,
+    SysInfo,
+    Battery,
+    Disk,
+    Clock,
     Plugin,
 }
 
@@ -125,14 +258,40 @@ impl ComponentType {
             "row" => ComponentType::Row,
             "text" => ComponentType::Text,
             "block" => ComponentType::Block,
+            "gauge" => ComponentType::Gauge,
+            "sparkline" => ComponentType::Sparkline,
+            "chart" => ComponentType::Chart,
+            "barchart" => ComponentType::BarChart,
+            "table" => ComponentType::Table,
+            "list" => ComponentType::List,
+            "exec" => ComponentType::Exec,
+            "sysinfo" => ComponentType::SysInfo,
+            "battery" => ComponentType::Battery,
+            "disk" => ComponentType::Disk,
+            "clock" => ComponentType::Clock,
             _ => ComponentType::Plugin,
         }
     }
 
+    /// Plugins are containers that own a store and a subroutine, their children are laid out
+    /// like a column
     pub fn is_layout(&self) -> bool {
+        self.is_plugin()
+            || matches!(
+                self,
+                ComponentType::Window | ComponentType::Column | ComponentType::Row
+            )
+    }
+
+    pub fn is_plugin(&self) -> bool {
         matches!(
             self,
-            ComponentType::Window | ComponentType::Column | ComponentType::Row
+            ComponentType::Exec
+                | ComponentType::SysInfo
+                | ComponentType::Battery
+                | ComponentType::Disk
+                | ComponentType::Clock
+                | ComponentType::Plugin
         )
     }
 
@@ -147,3 +306,39 @@ impl ComponentType {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attributes_outside_plugins() {
+        let read = |value: &str, missing: &MissingKeys| {
+            Attribute::create(value.to_string(), None, missing)
+                .read()
+                .unwrap()
+        };
+
+        assert_eq!(read("cpu {{cpu}}", &MissingKeys::Blank), "cpu ");
+        assert_eq!(
+            read("{{cpu}}", &MissingKeys::Placeholder("n/a".to_string())),
+            "n/a"
+        );
+        assert_eq!(read("{{cpu ?? 0}}", &MissingKeys::Error), "0");
+        assert_eq!(read(r"\{{cpu}} {{cpu}}", &MissingKeys::Blank), "{{cpu}} ");
+    }
+
+    #[test]
+    fn attributes_in_plugins() {
+        let store: Store = Arc::default();
+        let a = Attribute::create(
+            "{{cpu}}%".to_string(),
+            Some(store.clone()),
+            &MissingKeys::Blank,
+        );
+        assert_eq!(a.read().unwrap(), "%");
+
+        store.write().insert("cpu".to_string(), Value::from("12"));
+        assert_eq!(a.read().unwrap(), "12%");
+    }
+}
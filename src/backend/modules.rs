@@ -1,28 +1,61 @@
+use std::collections::BTreeMap;
+
 use ratatui::{
     buffer::Buffer,
-    layout::Rect,
-    style::{Color, Stylize},
-    text::Line,
-    widgets::{Block, BorderType, Borders, Paragraph, Widget, WidgetRef},
+    layout::{Constraint, Rect},
+    style::{Color, Style, Stylize},
+    symbols::Marker,
+    text::{Line, Span},
+    widgets::{
+        Axis, Bar, BarChart, BarGroup, Block, Cell, Chart, Dataset, Gauge, GraphType, LineGauge,
+        List as ListWidget, Paragraph, Row, Sparkline, Table, Widget, WidgetRef, Wrap,
+    },
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
 
 use crate::{
-    backend::{Attributes, ComponentType, Module, RenderCallback, Store, SubRoutine},
-    utils::{create_borders, get_border_type, parse_from_attributes, read_opt_attributes},
+    backend::{
+        Attribute, Attributes, ComponentType, History, Module, RenderCallback, Store, SubRoutine,
+        diagnostics, history,
+        markup::Segment,
+        plugins::{Battery, Clock, Disk, Exec, SysInfo},
+        theme::ThemeColor,
+        value::{Value, lookup},
+        xmlparser::size_from_str,
+    },
+    utils::{
+        List, Thresholds, alignment_from_name, alignment_from_str, bool_from_optstr, bool_from_str,
+        create_borders, get_border_type, parse_or_record, read_opt_attributes,
+    },
 };
 
 pub fn create_renderer(
     ct: &ComponentType,
+    tag: &str,
     store: Option<Store>,
+    history: Option<History>,
     attributes: Attributes,
+    segments: Vec<Segment>,
 ) -> RenderCallback {
     match ct {
         ComponentType::Column | ComponentType::Window | ComponentType::Row => {
-            Box::new(Layout::new(attributes))
+            Box::new(Layout::new(tag, attributes))
         }
-        ComponentType::Text => Box::new(Text::new(attributes)),
+        ComponentType::Text => Box::new(Text::new(attributes, segments)),
         ComponentType::Block => Box::new(BlockComp::new(attributes)),
-        ComponentType::Plugin => Box::new(Plugin {}),
+        ComponentType::Gauge => Box::new(GaugeComp::new(attributes)),
+        ComponentType::Sparkline => Box::new(SparklineComp::new(attributes, history)),
+        ComponentType::Chart => Box::new(ChartComp::new(attributes, history)),
+        ComponentType::BarChart => Box::new(BarChartComp::new(attributes, store)),
+        ComponentType::Table => Box::new(TableComp::new(attributes, store)),
+        ComponentType::List => Box::new(ListComp::new(attributes, store)),
+        // plugins are drawn like any other layout
+        ComponentType::Exec
+        | ComponentType::SysInfo
+        | ComponentType::Battery
+        | ComponentType::Disk
+        | ComponentType::Clock
+        | ComponentType::Plugin => Box::new(Layout::new(tag, attributes)),
     }
 }
 
@@ -31,19 +64,28 @@ pub fn get_subroutine(ct: &ComponentType) -> fn(&mut SubRoutine) {
         ComponentType::Column | ComponentType::Window | ComponentType::Row => Layout::subroutine,
         ComponentType::Text => Text::subroutine,
         ComponentType::Block => BlockComp::subroutine,
+        ComponentType::Gauge => GaugeComp::subroutine,
+        ComponentType::Sparkline => SparklineComp::subroutine,
+        ComponentType::Chart => ChartComp::subroutine,
+        ComponentType::BarChart => BarChartComp::subroutine,
+        ComponentType::Table => TableComp::subroutine,
+        ComponentType::List => ListComp::subroutine,
+        ComponentType::Exec => Exec::subroutine,
+        ComponentType::SysInfo => SysInfo::subroutine,
+        ComponentType::Battery => Battery::subroutine,
+        ComponentType::Disk => Disk::subroutine,
+        ComponentType::Clock => Clock::subroutine,
         ComponentType::Plugin => Plugin::subroutine,
     }
 }
 
 struct BlockComp {
-    fill: Option<Color>,
+    attributes: Attributes,
 }
 
 impl BlockComp {
     fn new(attributes: Attributes) -> Self {
-        let lock = attributes.read();
-        let fill = parse_from_attributes(lock.get("fill"));
-        Self { fill }
+        Self { attributes }
     }
 }
 
@@ -51,9 +93,11 @@ impl Module for BlockComp {}
 
 impl WidgetRef for BlockComp {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let lock = self.attributes.read();
+        let fill: Option<ThemeColor> = parse_or_record("block", "fill", &lock);
         let mut block = Block::new();
 
-        if let Some(f) = self.fill {
+        if let Some(f) = fill {
             block = block.bg(f)
         };
 
@@ -61,27 +105,534 @@ impl WidgetRef for BlockComp {
     }
 }
 
+/// Gauge max used when `max` is missing or not above zero
+const DEFAULT_GAUGE_MAX: f64 = 100.0;
+
+struct GaugeComp {
+    attributes: Attributes,
+}
+
+impl GaugeComp {
+    fn new(attributes: Attributes) -> Self {
+        Self { attributes }
+    }
+}
+
+impl Module for GaugeComp {}
+
+impl WidgetRef for GaugeComp {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let lock = self.attributes.read();
+        let value = finite_or_record("gauge", "value", &lock).unwrap_or_default();
+        let max = finite_or_record("gauge", "max", &lock)
+            .filter(|m| *m > 0.0)
+            .unwrap_or(DEFAULT_GAUGE_MAX);
+        let ratio = (value / max).clamp(0.0, 1.0);
+
+        // thresholds win over fill once the gauge is full enough
+        let thresholds: Option<Thresholds> = parse_or_record("gauge", "thresholds", &lock);
+        let color = thresholds
+            .and_then(|t| t.color(ratio * 100.0))
+            .or_else(|| parse_or_record::<ThemeColor>("gauge", "fill", &lock).map(Color::from));
+        let style = color.map(|c| Style::new().fg(c)).unwrap_or_default();
+
+        let label = read_opt_attributes(lock.get("label"));
+
+        if bool_from_optstr(read_opt_attributes(lock.get("line"))) {
+            let mut gauge = LineGauge::default().ratio(ratio).filled_style(style);
+            if let Some(l) = label {
+                gauge = gauge.label(l);
+            }
+            gauge.render(area, buf);
+        } else {
+            let mut gauge = Gauge::default()
+                .ratio(ratio)
+                .gauge_style(style)
+                .use_unicode(true);
+            if let Some(l) = label {
+                gauge = gauge.label(l);
+            }
+            gauge.render(area, buf);
+        }
+    }
+}
+
+/// `parse_or_record` for numbers, NaN and infinities are recorded as invalid too
+fn finite_or_record(tag: &str, name: &str, a: &BTreeMap<String, Attribute>) -> Option<f64> {
+    parse_or_record::<f64>(tag, name, a).filter(|v| {
+        if !v.is_finite() {
+            diagnostics::record(format!(
+                "<{tag}> {name}: Invalid value \"{v}\": not a number"
+            ));
+        }
+        v.is_finite()
+    })
+}
+
+/// Samples kept when `samples` is missing
+const DEFAULT_SAMPLES: usize = 60;
+/// Sparklines and bar charts draw integers, values are scaled up so small fractions (e.g. load)
+/// still show
+const INT_SCALE: f64 = 1000.0;
+/// Colours of the series in charts without `colors`
+const SERIES_COLORS: &[Color] = &[
+    Color::Green,
+    Color::Red,
+    Color::Blue,
+    Color::Yellow,
+    Color::Magenta,
+    Color::Cyan,
+];
+
+struct SparklineComp {
+    attributes: Attributes,
+    /// Store key the history is kept for
+    source: Option<String>,
+    /// The ring may keep more for other widgets watching the same key
+    samples: usize,
+    /// `None` outside of plugins, there is nothing to record there
+    history: Option<History>,
+}
+
+impl SparklineComp {
+    fn new(attributes: Attributes, history: Option<History>) -> Self {
+        let lock = attributes.read();
+        let source = read_opt_attributes(lock.get("source"));
+        let samples = parse_or_record("sparkline", "samples", &lock).unwrap_or(DEFAULT_SAMPLES);
+
+        if let (Some(h), Some(s)) = (&history, &source) {
+            history::watch(h, s, samples);
+        }
+        drop(lock);
+
+        Self {
+            attributes,
+            source,
+            samples,
+            history,
+        }
+    }
+}
+
+impl Module for SparklineComp {}
+
+impl WidgetRef for SparklineComp {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let (Some(history), Some(source)) = (&self.history, &self.source) else {
+            return;
+        };
+        let history = history.read();
+        let Some(ring) = history.get(source) else {
+            return;
+        };
+
+        // the newest samples that fit, sparklines draw from the left
+        let shown = self.samples.min(area.width as usize);
+        let skip = ring.iter().len().saturating_sub(shown);
+        let data: Vec<u64> = ring
+            .iter()
+            .skip(skip)
+            .map(|v| (v.max(0.0) * INT_SCALE) as u64)
+            .collect();
+
+        let lock = self.attributes.read();
+        let mut sparkline = Sparkline::default().data(&data);
+        if let Some(max) = parse_or_record::<f64>("sparkline", "max", &lock).filter(|m| *m > 0.0) {
+            sparkline = sparkline.max((max * INT_SCALE) as u64);
+        }
+        if let Some(fill) = parse_or_record::<ThemeColor>("sparkline", "fill", &lock) {
+            sparkline = sparkline.style(Style::new().fg(fill.into()));
+        }
+
+        sparkline.render(area, buf);
+    }
+}
+
+/// Comma separated store keys in `sources`
+fn read_sources(tag: &str, attributes: &Attributes) -> Vec<String> {
+    parse_or_record::<List<String>>(tag, "sources", &attributes.read())
+        .map(|l| l.0)
+        .unwrap_or_default()
+}
+
+/// Legend name and colour of every source, from `labels` and `colors`
+fn series_styles(
+    tag: &str,
+    sources: &[String],
+    lock: &BTreeMap<String, Attribute>,
+) -> Vec<(String, Color)> {
+    let labels: Vec<String> = parse_or_record::<List<String>>(tag, "labels", lock)
+        .map(|l| l.0)
+        .unwrap_or_default();
+    let colors: Vec<Color> = parse_or_record::<List<ThemeColor>>(tag, "colors", lock)
+        .map(|l| l.0.into_iter().map(Color::from).collect::<Vec<_>>())
+        .filter(|c| !c.is_empty())
+        .unwrap_or_else(|| SERIES_COLORS.to_vec());
+
+    sources
+        .iter()
+        .enumerate()
+        .map(|(i, s)| {
+            let label = labels.get(i).unwrap_or(s).clone();
+            (label, colors[i % colors.len()])
+        })
+        .collect()
+}
+
+/// Axis label, without decimals for large values
+fn format_number(v: f64) -> String {
+    if v.abs() >= 100.0 || v.fract() == 0.0 {
+        format!("{v:.0}")
+    } else {
+        format!("{v:.1}")
+    }
+}
+
+/// Several store keys plotted over time
+struct ChartComp {
+    attributes: Attributes,
+    sources: Vec<String>,
+    samples: usize,
+    /// `None` outside of plugins, there is nothing to record there
+    history: Option<History>,
+}
+
+impl ChartComp {
+    fn new(attributes: Attributes, history: Option<History>) -> Self {
+        let sources = read_sources("chart", &attributes);
+        let samples =
+            parse_or_record("chart", "samples", &attributes.read()).unwrap_or(DEFAULT_SAMPLES);
+
+        if let Some(h) = &history {
+            sources.iter().for_each(|s| history::watch(h, s, samples));
+        }
+
+        Self {
+            attributes,
+            sources,
+            samples,
+            history,
+        }
+    }
+}
+
+impl Module for ChartComp {}
+
+impl WidgetRef for ChartComp {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let Some(history) = &self.history else {
+            return;
+        };
+        let lock = self.attributes.read();
+
+        // newest sample at the right edge, x counts samples
+        let history = history.read();
+        let points: Vec<Vec<(f64, f64)>> = self
+            .sources
+            .iter()
+            .map(|s| {
+                let Some(ring) = history.get(s) else {
+                    return vec![];
+                };
+                // the ring may be shared with a widget keeping more samples
+                let len = ring.iter().len();
+                let skip = len.saturating_sub(self.samples);
+                let offset = self.samples.saturating_sub(len);
+                ring.iter()
+                    .skip(skip)
+                    .enumerate()
+                    .map(|(i, v)| ((offset + i) as f64, *v))
+                    .collect()
+            })
+            .collect();
+        drop(history);
+
+        let largest = points
+            .iter()
+            .flatten()
+            .map(|(_, y)| *y)
+            .fold(f64::NEG_INFINITY, f64::max);
+        let min: f64 = parse_or_record("chart", "min", &lock).unwrap_or(0.0);
+        let max: f64 = parse_or_record::<f64>("chart", "max", &lock)
+            .unwrap_or(largest)
+            .max(min + f64::EPSILON);
+
+        let datasets: Vec<Dataset> = series_styles("chart", &self.sources, &lock)
+            .into_iter()
+            .zip(&points)
+            .map(|((label, color), data)| {
+                Dataset::default()
+                    .name(label)
+                    .marker(Marker::Braille)
+                    .graph_type(GraphType::Line)
+                    .style(Style::new().fg(color))
+                    .data(data)
+            })
+            .collect();
+
+        let mut x_axis = Axis::default().bounds([0.0, self.samples.saturating_sub(1) as f64]);
+        if let Some(t) = read_opt_attributes(lock.get("xTitle")) {
+            x_axis = x_axis.title(t);
+        }
+        let mut y_axis = Axis::default()
+            .bounds([min, max])
+            .labels([format_number(min), format_number(max)]);
+        if let Some(t) = read_opt_attributes(lock.get("yTitle")) {
+            y_axis = y_axis.title(t);
+        }
+
+        let mut chart = Chart::new(datasets).x_axis(x_axis).y_axis(y_axis);
+        if read_opt_attributes(lock.get("legend")).and_then(|l| bool_from_str(&l)) == Some(false) {
+            chart = chart.legend_position(None);
+        }
+
+        chart.render(area, buf);
+    }
+}
+
+/// Latest value of several store keys, one bar each
+struct BarChartComp {
+    attributes: Attributes,
+    sources: Vec<String>,
+    /// `None` outside of plugins
+    store: Option<Store>,
+}
+
+impl BarChartComp {
+    fn new(attributes: Attributes, store: Option<Store>) -> Self {
+        let sources = read_sources("barchart", &attributes);
+
+        Self {
+            attributes,
+            sources,
+            store,
+        }
+    }
+}
+
+impl Module for BarChartComp {}
+
+impl WidgetRef for BarChartComp {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let (Some(store), false) = (&self.store, self.sources.is_empty()) else {
+            return;
+        };
+        let lock = self.attributes.read();
+        let store = store.read();
+
+        let bars: Vec<Bar> = series_styles("barchart", &self.sources, &lock)
+            .into_iter()
+            .zip(&self.sources)
+            .map(|((label, color), source)| {
+                let value = lookup(&store, source)
+                    .and_then(|v| v.as_number())
+                    .unwrap_or_default();
+                Bar::default()
+                    .value((value.max(0.0) * INT_SCALE) as u64)
+                    .text_value(format_number(value))
+                    .label(Line::from(label))
+                    .style(Style::new().fg(color))
+            })
+            .collect();
+        drop(store);
+
+        // bars share the width unless told otherwise
+        let gap = 1;
+        let count = bars.len() as u16;
+        let width = parse_or_record("barchart", "barWidth", &lock)
+            .unwrap_or_else(|| (area.width.saturating_sub(gap * (count - 1)) / count).max(1));
+
+        let mut chart = BarChart::default()
+            .data(BarGroup::default().bars(&bars))
+            .bar_width(width)
+            .bar_gap(gap);
+        if let Some(max) = parse_or_record::<f64>("barchart", "max", &lock).filter(|m| *m > 0.0) {
+            chart = chart.max((max * INT_SCALE) as u64);
+        }
+
+        chart.render(area, buf);
+    }
+}
+
+/// Rows of a table, items of a list
+struct TableComp {
+    attributes: Attributes,
+    /// `None` outside of plugins
+    store: Option<Store>,
+}
+
+impl TableComp {
+    fn new(attributes: Attributes, store: Option<Store>) -> Self {
+        Self { attributes, store }
+    }
+}
+
+impl Module for TableComp {}
+
+impl WidgetRef for TableComp {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let lock = self.attributes.read();
+        let Some(source) = read_opt_attributes(lock.get("source")) else {
+            return;
+        };
+        let store = store.read();
+        let Some(value) = lookup(&store, &source) else {
+            return;
+        };
+        let items = value.items();
+
+        // every field of the first row unless told otherwise
+        let columns: Vec<String> = parse_or_record::<List<String>>("table", "columns", &lock)
+            .map(|l| l.0)
+            .unwrap_or_else(|| match items.first() {
+                Some(Value::Map(m)) => m.keys().cloned().collect(),
+                _ => vec![String::new()],
+            });
+
+        let rows: Vec<Row> = items
+            .iter()
+            .map(|item| {
+                Row::new(columns.iter().map(|c| match item {
+                    Value::Text(_) => Cell::from(item.to_string()),
+                    _ => Cell::from(item.get_path(c).map(|v| v.to_string()).unwrap_or_default()),
+                }))
+            })
+            .collect();
+        drop(store);
+
+        let widths: Vec<Constraint> = read_opt_attributes(lock.get("widths"))
+            .map(|w| {
+                w.split(',')
+                    .filter_map(|s| {
+                        size_from_str(s.trim())
+                            .inspect_err(|e| {
+                                diagnostics::record(format!(
+                                    "<table> widths: Invalid value \"{s}\": {e}"
+                                ))
+                            })
+                            .ok()
+                    })
+                    .collect()
+            })
+            .unwrap_or_default();
+        let widths =
+            (0..columns.len()).map(|i| widths.get(i).copied().unwrap_or(Constraint::Fill(1)));
+
+        let mut table = Table::new(rows, widths).column_spacing(1);
+        if read_opt_attributes(lock.get("header")).and_then(|h| bool_from_str(&h)) != Some(false) {
+            let titles = parse_or_record::<List<String>>("table", "titles", &lock)
+                .map(|l| l.0)
+                .unwrap_or_else(|| columns.clone());
+            table = table.header(Row::new(titles).bold());
+        }
+
+        Widget::render(table, area, buf);
+    }
+}
+
+struct ListComp {
+    attributes: Attributes,
+    /// `None` outside of plugins
+    store: Option<Store>,
+}
+
+impl ListComp {
+    fn new(attributes: Attributes, store: Option<Store>) -> Self {
+        Self { attributes, store }
+    }
+}
+
+impl Module for ListComp {}
+
+impl WidgetRef for ListComp {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        let Some(store) = &self.store else {
+            return;
+        };
+        let Some(source) = read_opt_attributes(self.attributes.read().get("source")) else {
+            return;
+        };
+        let store = store.read();
+        let Some(value) = lookup(&store, &source) else {
+            return;
+        };
+
+        let list = ListWidget::new(value.items().iter().map(|i| i.to_string()));
+        Widget::render(list, area, buf);
+    }
+}
+
 /* Layout */
 struct Layout {
-    borders: Borders,
-    btype: BorderType,
+    /// Tag as written, unknown plugins all share `ComponentType::Plugin`
+    tag: String,
+    attributes: Attributes,
+    /// Every title of `title`, templated separately
+    titles: Vec<Attribute>,
 }
 
 impl Layout {
-    fn new(attributes: Attributes) -> Self {
-        let lock = attributes.read();
-        let borders = create_borders(lock.get("border"));
-        let btype = get_border_type(lock.get("borderType"));
+    fn new(tag: &str, attributes: Attributes) -> Self {
+        let titles = attributes
+            .read()
+            .get("title")
+            .map(|t| t.split(';'))
+            .unwrap_or_default();
 
-        Self { borders, btype }
+        Self {
+            tag: tag.to_string(),
+            attributes,
+            titles,
+        }
     }
 }
 
+/// Entry `i` of a `;` separated list, lists that are too short repeat their last entry
+fn nth_or_last(list: &str, i: usize) -> Option<&str> {
+    let entries: Vec<&str> = list.split(';').map(str::trim).collect();
+    entries.get(i).or(entries.last()).copied()
+}
+
 impl Module for Layout {}
 
 impl WidgetRef for Layout {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
-        let block = Block::new().borders(self.borders).border_type(self.btype);
+        let lock = self.attributes.read();
+        let mut block = Block::new()
+            .borders(create_borders(lock.get("border")))
+            .border_type(get_border_type(lock.get("borderType")));
+
+        // children are drawn on top, so they keep these unless they set their own
+        let mut style = Style::new();
+        if let Some(bg) = parse_or_record::<ThemeColor>(&self.tag, "bg", &lock) {
+            style = style.bg(bg.into());
+        }
+        if let Some(fg) = parse_or_record::<ThemeColor>(&self.tag, "fg", &lock) {
+            style = style.fg(fg.into());
+        }
+        block = block.style(style);
+
+        if let Some(color) = parse_or_record::<ThemeColor>(&self.tag, "borderColor", &lock) {
+            block = block.border_style(Style::new().fg(color.into()));
+        }
+
+        let aligns = read_opt_attributes(lock.get("titleAlign")).unwrap_or_default();
+        let positions = read_opt_attributes(lock.get("titlePosition")).unwrap_or_default();
+        for (i, title) in self.titles.iter().enumerate() {
+            let Some(text) = read_opt_attributes(Some(title)).filter(|t| !t.is_empty()) else {
+                continue;
+            };
+            let line = Line::from(text).alignment(alignment_from_name(nth_or_last(&aligns, i)));
+
+            block = match nth_or_last(&positions, i) {
+                Some("bottom") => block.title_bottom(line),
+                _ => block.title_top(line),
+            };
+        }
+
         block.render(area, buf);
     }
 }
@@ -89,29 +640,110 @@ impl WidgetRef for Layout {
 #[derive(Default)]
 struct Text {
     attributes: Attributes,
+    segments: Vec<Segment>,
 }
 
 impl Text {
-    fn new(attributes: Attributes) -> Self {
-        Self { attributes }
+    fn new(attributes: Attributes, segments: Vec<Segment>) -> Self {
+        Self {
+            attributes,
+            segments,
+        }
+    }
+}
+
+/// Cut `line` down to `width` columns, ending with `ellipsis` when anything was cut
+fn truncate_line(line: Line<'static>, width: usize, ellipsis: &str) -> Line<'static> {
+    if line.width() <= width {
+        return line;
+    }
+
+    let budget = width.saturating_sub(ellipsis.width());
+    let mut used = 0;
+    let mut style = Style::default();
+    let mut spans = vec![];
+
+    'spans: for span in line.spans {
+        style = span.style;
+        let mut content = String::new();
+        for c in span.content.chars() {
+            let w = c.width().unwrap_or(0);
+            if used + w > budget {
+                spans.push(Span::styled(content, style));
+                break 'spans;
+            }
+            used += w;
+            content.push(c);
+        }
+        spans.push(Span::styled(content, style));
     }
+
+    // the ellipsis continues the style of the text it replaces
+    spans.push(Span::styled(ellipsis.to_string(), style));
+    Line::from(spans)
 }
 
 impl Module for Text {}
 
 impl WidgetRef for Text {
     fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        // convert the segments into Lines, a segment may span several lines
+        let mut text_widgets: Vec<Line> = vec![Line::default()];
+        for segment in &self.segments {
+            let Some(text) = read_opt_attributes(Some(&segment.text)) else {
+                continue;
+            };
+            let style = segment.style.resolve();
+
+            for (i, part) in text.split('\n').enumerate() {
+                if i > 0 {
+                    text_widgets.push(Line::default());
+                }
+                if !part.is_empty() {
+                    text_widgets
+                        .last_mut()
+                        .unwrap()
+                        .push_span(Span::styled(part.to_string(), style));
+                }
+            }
+        }
+
         let lock = self.attributes.read();
-        // store attr
-        let text = read_opt_attributes(lock.get("text"));
+        let wrap = read_opt_attributes(lock.get("wrap"));
+        let wrap = match wrap.as_deref() {
+            Some("true") => Some(Wrap { trim: false }),
+            Some("trim") => Some(Wrap { trim: true }),
+            _ => None,
+        };
 
-        // convert text into Lines
-        let mut text_widgets: Vec<Line> = vec![];
-        if let Some(t) = text {
-            text_widgets = t.split('\n').map(|s| Line::from(s.to_string())).collect();
+        // only lines that don't wrap can be cut
+        if wrap.is_none()
+            && let Some(ellipsis) = read_opt_attributes(lock.get("ellipsis"))
+        {
+            text_widgets = text_widgets
+                .into_iter()
+                .map(|l| truncate_line(l, area.width as usize, &ellipsis))
+                .collect();
         }
 
-        let pg = Paragraph::new(text_widgets);
+        let mut pg = Paragraph::new(text_widgets).alignment(alignment_from_str(lock.get("align")));
+        if let Some(w) = wrap {
+            pg = pg.wrap(w);
+        }
+
+        // move the area down to the lines that are actually drawn
+        let height = (pg.line_count(area.width) as u16).min(area.height);
+        let free = area.height - height;
+        let offset = match read_opt_attributes(lock.get("valign")).as_deref() {
+            Some("center") => free / 2,
+            Some("bottom") => free,
+            _ => 0,
+        };
+        let area = Rect {
+            y: area.y + offset,
+            height: area.height - offset,
+            ..area
+        };
 
         // useful for debugging
         //pg = pg.block(Block::bordered().border_style(Style::new().fg(Color::Green)));
@@ -124,7 +756,3 @@ impl WidgetRef for Text {
 struct Plugin {}
 
 impl Module for Plugin {}
-
-impl WidgetRef for Plugin {
-    fn render_ref(&self, area: Rect, buf: &mut Buffer) {}
-}
@@ -0,0 +1,260 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use crate::{
+    backend::{Module, SubRoutine},
+    utils::read_opt_attributes,
+};
+
+const DEFAULT_PATH: &str = "/sys/class/power_supply";
+
+/// Publishes every power supply found in `path` (defaults to `/sys/class/power_supply`)
+///
+/// Store keys, per supply (e.g. `BAT0.capacity`):
+/// - `NAME.type`, `NAME.online`
+/// - `NAME.capacity` in percent, `NAME.status` (charging, discharging, full, ...)
+/// - `NAME.energy_now`, `NAME.energy_full` in µWh, `NAME.power_now` in µW
+/// - `NAME.time_remaining` as `H:MM`, `NAME.time_remaining.secs`, when the kernel reports the
+///   power draw
+///
+/// `capacity`, `status` and `time_remaining` are also published without a prefix for the first
+/// battery, `ac` is `1` when any mains supply is online.
+pub struct Battery;
+
+impl Module for Battery {
+    fn subroutine(routine: &mut SubRoutine) {
+        let path = read_opt_attributes(routine.attributes.read().get("path"))
+            .unwrap_or_else(|| DEFAULT_PATH.to_string());
+
+        // supplies come and go, so do their keys
+        routine.replace(read_supplies(Path::new(&path)).unwrap_or_default());
+    }
+}
+
+/// Store values for every supply in `path`
+fn read_supplies(path: &Path) -> Option<BTreeMap<String, String>> {
+    let entries = fs::read_dir(path).ok()?;
+    let mut supplies: Vec<_> = entries.filter_map(|e| e.ok()).map(|e| e.path()).collect();
+    supplies.sort();
+
+    let mut out: BTreeMap<String, String> = BTreeMap::new();
+    let mut first_battery = true;
+    let mut ac = false;
+
+    for supply in supplies {
+        let Some(name) = supply.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let values = read_supply(&supply);
+
+        match values.get("type").map(|t| t.as_str()) {
+            Some("Mains") => ac |= values.get("online").is_some_and(|o| o == "1"),
+            Some("Battery") if first_battery => {
+                first_battery = false;
+                for key in ["capacity", "status", "time_remaining"] {
+                    if let Some(v) = values.get(key) {
+                        out.insert(key.to_string(), v.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        out.extend(values.into_iter().map(|(k, v)| (format!("{name}.{k}"), v)));
+    }
+
+    out.insert("ac".to_string(), (ac as u8).to_string());
+    Some(out)
+}
+
+fn read_supply(supply: &Path) -> BTreeMap<String, String> {
+    let read = |file: &str| {
+        fs::read_to_string(supply.join(file))
+            .ok()
+            .map(|v| v.trim().to_string())
+    };
+
+    let mut values = BTreeMap::new();
+    for file in [
+        "type",
+        "online",
+        "capacity",
+        "energy_now",
+        "energy_full",
+        "power_now",
+    ] {
+        if let Some(v) = read(file) {
+            values.insert(file.to_string(), v);
+        }
+    }
+    if let Some(status) = read("status") {
+        values.insert("status".to_string(), status.to_lowercase());
+    }
+
+    // some batteries report charge (µAh) and current (µA) instead of energy and power
+    let number = |a: &str, b: &str| read(a).or_else(|| read(b))?.parse::<f64>().ok();
+    let now = number("energy_now", "charge_now");
+    let full = number("energy_full", "charge_full");
+    let rate = number("power_now", "current_now").filter(|r| *r > 0.0);
+
+    let remaining = match (values.get("status").map(|s| s.as_str()), now, full, rate) {
+        (Some("discharging"), Some(now), _, Some(rate)) => Some(now / rate),
+        (Some("charging"), Some(now), Some(full), Some(rate)) => Some((full - now).max(0.0) / rate),
+        _ => None,
+    };
+
+    if let Some(hours) = remaining {
+        let secs = (hours * 3600.0) as u64;
+        values.insert("time_remaining.secs".to_string(), secs.to_string());
+        values.insert(
+            "time_remaining".to_string(),
+            format!("{}:{:02}", secs / 3600, secs % 3600 / 60),
+        );
+    }
+
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, sync::Arc, time::Duration};
+
+    use parking_lot::RwLock;
+
+    use super::*;
+    use crate::backend::{Attribute, template::MissingKeys};
+
+    /// A fake `power_supply` directory, removed when dropped
+    struct Supplies(PathBuf);
+
+    impl Supplies {
+        fn new(name: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("rpanel-{}-{name}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            fs::create_dir_all(&dir).unwrap();
+            Self(dir)
+        }
+
+        fn add(&self, supply: &str, files: &[(&str, &str)]) {
+            let dir = self.0.join(supply);
+            fs::create_dir(&dir).unwrap();
+            for (file, contents) in files {
+                fs::write(dir.join(file), format!("{contents}\n")).unwrap();
+            }
+        }
+    }
+
+    impl Drop for Supplies {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn discharging() {
+        let s = Supplies::new("discharging");
+        s.add("AC", &[("type", "Mains"), ("online", "0")]);
+        s.add(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("capacity", "50"),
+                ("energy_now", "30000000"),
+                ("energy_full", "60000000"),
+                ("power_now", "20000000"),
+            ],
+        );
+
+        let out = read_supplies(&s.0).unwrap();
+        assert_eq!(out["ac"], "0");
+        assert_eq!(out["capacity"], "50");
+        assert_eq!(out["status"], "discharging");
+        assert_eq!(out["time_remaining"], "1:30");
+        assert_eq!(out["BAT0.time_remaining.secs"], "5400");
+        assert_eq!(out["AC.online"], "0");
+    }
+
+    #[test]
+    fn charging() {
+        let s = Supplies::new("charging");
+        s.add("AC", &[("type", "Mains"), ("online", "1")]);
+        // charge and current instead of energy and power
+        s.add(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Charging"),
+                ("capacity", "75"),
+                ("charge_now", "3000000"),
+                ("charge_full", "4000000"),
+                ("current_now", "2000000"),
+            ],
+        );
+        s.add("BAT1", &[("type", "Battery"), ("capacity", "10")]);
+
+        let out = read_supplies(&s.0).unwrap();
+        assert_eq!(out["ac"], "1");
+        assert_eq!(out["capacity"], "75");
+        assert_eq!(out["status"], "charging");
+        assert_eq!(out["time_remaining"], "0:30");
+        assert_eq!(out["BAT1.capacity"], "10");
+        assert!(!out.contains_key("BAT1.time_remaining"));
+    }
+
+    #[test]
+    fn stale_keys() {
+        let s = Supplies::new("stale");
+        s.add("AC", &[("type", "Mains"), ("online", "0")]);
+        s.add(
+            "BAT0",
+            &[
+                ("type", "Battery"),
+                ("status", "Discharging"),
+                ("energy_now", "30000000"),
+                ("power_now", "20000000"),
+            ],
+        );
+
+        let path = Attribute::create(
+            s.0.to_string_lossy().to_string(),
+            None,
+            &MissingKeys::default(),
+        );
+        let mut routine = SubRoutine::new(
+            Arc::default(),
+            Arc::default(),
+            Arc::new(RwLock::new(BTreeMap::from([("path".to_string(), path)]))),
+            Duration::from_secs(1),
+            false,
+            Battery::subroutine,
+        );
+        routine.tick();
+        assert!(routine.store.read().contains_key("time_remaining"));
+
+        // unplugged and no longer drawing power
+        fs::remove_dir_all(s.0.join("AC")).unwrap();
+        fs::write(s.0.join("BAT0/status"), "Full\n").unwrap();
+        fs::write(s.0.join("BAT0/power_now"), "0\n").unwrap();
+        routine.tick();
+
+        let store = routine.store.read();
+        assert_eq!(store["status"].to_string(), "full");
+        assert!(!store.contains_key("time_remaining"));
+        assert!(!store.contains_key("BAT0.time_remaining.secs"));
+        assert!(!store.contains_key("AC.online"));
+    }
+
+    #[test]
+    fn no_power_draw() {
+        let s = Supplies::new("idle");
+        s.add(
+            "BAT0",
+            &[("type", "Battery"), ("status", "Full"), ("power_now", "0")],
+        );
+
+        let out = read_supplies(&s.0).unwrap();
+        assert_eq!(out["status"], "full");
+        assert!(!out.contains_key("time_remaining"));
+        assert!(read_supplies(&s.0.join("missing")).is_none());
+    }
+}
@@ -0,0 +1,83 @@
+use jiff::{Timestamp, Zoned, fmt::strtime, tz::TimeZone};
+
+use crate::{
+    backend::{Module, SubRoutine},
+    utils::read_opt_attributes,
+};
+
+const DEFAULT_TIME: &str = "%H:%M:%S";
+const DEFAULT_DATE: &str = "%Y-%m-%d";
+
+/// Publishes the current time, formatted with strftime
+///
+/// Store keys:
+/// - `time`: formatted with `format`, defaults to `%H:%M:%S`
+/// - `date`: formatted as `%Y-%m-%d`
+/// - `timestamp`: seconds since the epoch
+/// - any `name` listed in `formats`, e.g. `formats="day=%A;week=%V"`
+///
+/// `tz` takes an IANA time zone such as `Europe/Stockholm` and defaults to the system time zone.
+/// Clocks tick aligned to the wall clock unless `alignTicks="false"` is set.
+pub struct Clock;
+
+impl Module for Clock {
+    fn subroutine(routine: &mut SubRoutine) {
+        let (format, formats, tz) = {
+            let lock = routine.attributes.read();
+            (
+                read_opt_attributes(lock.get("format")),
+                read_opt_attributes(lock.get("formats")),
+                read_opt_attributes(lock.get("tz")),
+            )
+        };
+
+        let tz = match tz {
+            Some(name) => TimeZone::get(&name).unwrap_or(TimeZone::UTC),
+            None => TimeZone::system(),
+        };
+        let now = Timestamp::now().to_zoned(tz);
+
+        let mut named = vec![
+            (
+                "time".to_string(),
+                format.unwrap_or_else(|| DEFAULT_TIME.to_string()),
+            ),
+            ("date".to_string(), DEFAULT_DATE.to_string()),
+        ];
+        named.extend(parse_formats(&formats.unwrap_or_default()));
+
+        let mut out: Vec<(String, String)> = named
+            .into_iter()
+            .map(|(name, fmt)| {
+                // invalid formats are caught by validation, unless they came from a template
+                let value = strtime::format(&fmt, &now).unwrap_or_else(|e| e.to_string());
+                (name, value)
+            })
+            .collect();
+        out.push((
+            "timestamp".to_string(),
+            now.timestamp().as_second().to_string(),
+        ));
+        routine.publish(out);
+    }
+}
+
+/// `name=format;name=format`
+pub fn parse_formats(formats: &str) -> Vec<(String, String)> {
+    formats
+        .split(';')
+        .filter_map(|f| {
+            let (name, fmt) = f.split_once('=')?;
+            Some((name.trim().to_string(), fmt.to_string()))
+        })
+        .filter(|(name, _)| !name.is_empty())
+        .collect()
+}
+
+/// Check a strftime format by formatting the epoch with it
+pub fn check_format(fmt: &str) -> Result<(), String> {
+    let sample = Zoned::new(Timestamp::UNIX_EPOCH, TimeZone::UTC);
+    strtime::format(fmt, &sample)
+        .map(|_| ())
+        .map_err(|e| e.to_string())
+}
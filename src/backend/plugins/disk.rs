@@ -0,0 +1,189 @@
+use std::{
+    collections::BTreeMap,
+    ffi::CString,
+    fs,
+    mem::MaybeUninit,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use crate::{
+    backend::{Module, SubRoutine, value::Value},
+    utils::read_opt_attributes,
+};
+
+/// diskstats always counts in 512 byte sectors
+const SECTOR_SIZE: u64 = 512;
+
+/// Publishes usage and throughput of the mounts in `mounts`, a comma separated list of paths
+///
+/// Keys are namespaced by the mount, `/` is `root` and every other mount is its path without
+/// the leading slash and with the remaining slashes replaced by `_` (`/mnt/data` is
+/// `mnt_data`). A mount can also be named explicitly with `name=path`.
+///
+/// Store keys:
+/// - `NAME.total`, `NAME.used`, `NAME.free` in bytes, `NAME.used_pct`
+/// - `NAME.read`, `NAME.write` in bytes per second, from the second tick on
+/// - `mounts`: the same values as a list with a row per mount, which also has `name` and `path`
+pub struct Disk;
+
+#[derive(Default)]
+struct State {
+    /// `(sectors read, sectors written)` per mount name
+    io: BTreeMap<String, (u64, u64)>,
+    last: Option<Instant>,
+}
+
+impl Module for Disk {
+    fn subroutine(routine: &mut SubRoutine) {
+        let mounts = read_opt_attributes(routine.attributes.read().get("mounts"))
+            .unwrap_or_else(|| "/".to_string());
+        let mounts: Vec<(String, PathBuf)> = mounts
+            .split(',')
+            .map(|m| m.trim())
+            .filter(|m| !m.is_empty())
+            .map(|m| match m.split_once('=') {
+                Some((name, path)) => (name.trim().to_string(), PathBuf::from(path.trim())),
+                None => (mount_name(m), PathBuf::from(m)),
+            })
+            .collect();
+
+        let state = routine.state.get::<State>();
+        let mut out: BTreeMap<String, String> = BTreeMap::new();
+
+        for (name, path) in &mounts {
+            let Some((total, free, avail)) = statvfs(path) else {
+                continue;
+            };
+            let used = total.saturating_sub(free);
+            // like df, the reserved blocks count as neither used nor available
+            let usable = used + avail;
+            let pct = if usable == 0 {
+                0.0
+            } else {
+                used as f64 / usable as f64 * 100.0
+            };
+
+            out.insert(format!("{name}.total"), total.to_string());
+            out.insert(format!("{name}.used"), used.to_string());
+            out.insert(format!("{name}.free"), avail.to_string());
+            out.insert(format!("{name}.used_pct"), format!("{pct:.1}"));
+        }
+
+        throughput(state, &mounts, &mut out);
+
+        let rows: Vec<Value> = mounts
+            .iter()
+            .filter(|(name, _)| out.contains_key(&format!("{name}.total")))
+            .map(|(name, path)| {
+                let mut row: BTreeMap<String, Value> = out
+                    .iter()
+                    .filter_map(|(k, v)| {
+                        let field = k.strip_prefix(name.as_str())?.strip_prefix('.')?;
+                        Some((field.to_string(), Value::from(v.as_str())))
+                    })
+                    .collect();
+                row.insert("name".to_string(), Value::from(name.as_str()));
+                row.insert("path".to_string(), Value::from(path.display().to_string()));
+                Value::from(row)
+            })
+            .collect();
+
+        routine.publish(out);
+        routine.publish([("mounts".to_string(), rows)]);
+    }
+}
+
+fn mount_name(path: &str) -> String {
+    match path.trim_matches('/') {
+        "" => "root".to_string(),
+        p => p.replace('/', "_"),
+    }
+}
+
+/// `(total, free, available)` bytes of the filesystem at `path`
+fn statvfs(path: &Path) -> Option<(u64, u64, u64)> {
+    let c_path = CString::new(path.as_os_str().as_encoded_bytes()).ok()?;
+    let mut stat = MaybeUninit::<libc::statvfs>::uninit();
+
+    // SAFETY: the path is NUL terminated and stat is only read when the call succeeded
+    let stat = unsafe {
+        if libc::statvfs(c_path.as_ptr(), stat.as_mut_ptr()) != 0 {
+            return None;
+        }
+        stat.assume_init()
+    };
+
+    let frsize = stat.f_frsize as u64;
+    Some((
+        stat.f_blocks as u64 * frsize,
+        stat.f_bfree as u64 * frsize,
+        stat.f_bavail as u64 * frsize,
+    ))
+}
+
+/// Block device (as named in diskstats) of every mount point
+fn mount_devices() -> BTreeMap<PathBuf, String> {
+    let Ok(mounts) = fs::read_to_string("/proc/self/mounts") else {
+        return BTreeMap::new();
+    };
+
+    mounts
+        .lines()
+        .filter_map(|l| {
+            let mut fields = l.split_whitespace();
+            let device = fields.next()?;
+            let mount = fields.next()?;
+            if !device.starts_with("/dev/") {
+                return None;
+            }
+            // /dev/mapper/... and friends are symlinks to the real device
+            let device = fs::canonicalize(device).ok()?;
+            let device = device.file_name()?.to_string_lossy().to_string();
+            Some((PathBuf::from(mount), device))
+        })
+        .collect()
+}
+
+fn throughput(state: &mut State, mounts: &[(String, PathBuf)], out: &mut BTreeMap<String, String>) {
+    let Ok(diskstats) = fs::read_to_string("/proc/diskstats") else {
+        return;
+    };
+    let elapsed = state.last.map(|l| l.elapsed().as_secs_f64());
+    state.last = Some(Instant::now());
+
+    let devices = mount_devices();
+    let io: BTreeMap<&str, (u64, u64)> = diskstats
+        .lines()
+        .filter_map(|l| {
+            let fields: Vec<&str> = l.split_whitespace().collect();
+            // major, minor, name, then reads completed, reads merged, sectors read, ...
+            let read = fields.get(5)?.parse().ok()?;
+            let written = fields.get(9)?.parse().ok()?;
+            Some((*fields.get(2)?, (read, written)))
+        })
+        .collect();
+
+    for (name, path) in mounts {
+        let Some(&(read, written)) = devices.get(path).and_then(|d| io.get(d.as_str())) else {
+            continue;
+        };
+
+        let prev = state.io.insert(name.clone(), (read, written));
+        let (Some((prev_read, prev_written)), Some(elapsed)) = (prev, elapsed) else {
+            continue;
+        };
+        if elapsed <= 0.0 {
+            continue;
+        }
+
+        let rate = |now: u64, prev: u64| {
+            format!(
+                "{:.0}",
+                (now.saturating_sub(prev) * SECTOR_SIZE) as f64 / elapsed
+            )
+        };
+        out.insert(format!("{name}.read"), rate(read, prev_read));
+        out.insert(format!("{name}.write"), rate(written, prev_written));
+    }
+}
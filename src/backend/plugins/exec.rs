@@ -0,0 +1,131 @@
+use std::{
+    io::Read,
+    os::unix::process::CommandExt,
+    process::{Child, Command, Stdio},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{
+    backend::{Module, SubRoutine, value::Value},
+    utils::{duration_from_str, read_opt_attributes},
+};
+
+/// Commands running longer than this are killed, unless `timeout` says otherwise
+const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+/// How often a running command is checked on
+const POLL_RATE: Duration = Duration::from_millis(20);
+
+/// Runs `cmd` through `sh -c` on every tick
+///
+/// Store keys:
+/// - `stdout`: standard output, without the trailing newline
+/// - `stderr`: standard error, without the trailing newline
+/// - `code`: exit code, `-1` if the command could not be run, was killed by a signal or timed out
+/// - `lines`: every line of standard output, as a list
+///
+/// Commands are killed, along with everything they started, once they run longer than `timeout`
+/// (10 seconds by default) or the layout is reloaded. Anything they leave running in the
+/// background is killed when they exit.
+pub struct Exec;
+
+impl Module for Exec {
+    fn subroutine(routine: &mut SubRoutine) {
+        let (cmd, timeout) = {
+            let lock = routine.attributes.read();
+            (
+                read_opt_attributes(lock.get("cmd")),
+                read_opt_attributes(lock.get("timeout")),
+            )
+        };
+        let Some(cmd) = cmd else {
+            return;
+        };
+        let timeout = timeout
+            .and_then(|t| duration_from_str(&t).ok())
+            .unwrap_or(DEFAULT_TIMEOUT);
+
+        let (stdout, stderr, code) = match run(&cmd, timeout, routine) {
+            Ok(out) => out,
+            // stopped, nobody is looking at the store anymore
+            Err(None) => return,
+            Err(Some(e)) => (String::new(), e, -1),
+        };
+
+        let stdout = stdout.trim_end_matches('\n');
+        let lines: Vec<Value> = stdout.lines().map(Value::from).collect();
+
+        routine.publish([
+            ("stdout".to_string(), Value::from(stdout)),
+            (
+                "stderr".to_string(),
+                Value::from(stderr.trim_end_matches('\n')),
+            ),
+            ("code".to_string(), Value::from(code.to_string())),
+            ("lines".to_string(), Value::from(lines)),
+        ]);
+    }
+}
+
+/// Run `cmd` until it exits, times out or the routine is stopped
+///
+/// Returns stdout, stderr and the exit code. Errors are `None` when the routine was stopped.
+fn run(
+    cmd: &str,
+    timeout: Duration,
+    routine: &SubRoutine,
+) -> Result<(String, String, i32), Option<String>> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(cmd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        // own process group, so whatever the command starts can be killed with it
+        .process_group(0)
+        .spawn()
+        .map_err(|e| Some(e.to_string()))?;
+
+    // read while waiting, a command filling up a pipe would never exit otherwise
+    let stdout = read_pipe(child.stdout.take());
+    let stderr = read_pipe(child.stderr.take());
+
+    let started = Instant::now();
+    let res = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Ok(status.code().unwrap_or(-1)),
+            Ok(None) if routine.stopped() => break Err(None),
+            Ok(None) if started.elapsed() >= timeout => {
+                break Err(Some(format!("timed out after {}s", timeout.as_secs_f64())));
+            }
+            Ok(None) => thread::sleep(POLL_RATE),
+            Err(e) => break Err(Some(e.to_string())),
+        }
+    };
+
+    // anything left in the background could hold the pipes open forever
+    kill(&mut child);
+    let stdout = stdout.join().unwrap_or_default();
+    let stderr = stderr.join().unwrap_or_default();
+
+    res.map(|code| (stdout, stderr, code))
+}
+
+fn read_pipe<R: Read + Send + 'static>(pipe: Option<R>) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut buf = vec![];
+        if let Some(mut p) = pipe {
+            let _ = p.read_to_end(&mut buf);
+        }
+        String::from_utf8_lossy(&buf).to_string()
+    })
+}
+
+/// Kill the whole process group of `child` and reap it, if that wasn't done already
+fn kill(child: &mut Child) {
+    // SAFETY: only sends a signal, the group was created for this command
+    unsafe {
+        libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+    }
+    let _ = child.wait();
+}
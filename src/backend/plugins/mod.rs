@@ -0,0 +1,16 @@
+//! Built-in plugins
+//!
+//! Plugins own a store which their subroutine fills with data, children of a plugin can template
+//! from it through `{{key}}`
+
+mod battery;
+mod clock;
+mod disk;
+mod exec;
+mod sysinfo;
+
+pub use battery::Battery;
+pub use clock::{Clock, check_format, parse_formats};
+pub use disk::Disk;
+pub use exec::Exec;
+pub use sysinfo::SysInfo;
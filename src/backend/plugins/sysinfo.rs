@@ -0,0 +1,274 @@
+use std::{collections::BTreeMap, fs, time::Instant};
+
+use crate::{
+    backend::{Module, SubRoutine, value::Value},
+    utils::read_opt_attributes,
+};
+
+/// Processes listed in `procs` when the `procs` attribute is missing
+const DEFAULT_PROCS: usize = 10;
+
+/// Publishes system stats read from `/proc`
+///
+/// Store keys:
+/// - `cpu`, `cpu.N`: total and per core usage in percent, `cpu.count`: number of cores
+/// - `mem.used`, `mem.total`, `mem.available` in bytes, `mem.used_pct`
+/// - `swap.used`, `swap.total` in bytes, `swap.used_pct`
+/// - `load.1`, `load.5`, `load.15`
+/// - `uptime` in seconds, `uptime.human` e.g. `3d 4h 12m`
+/// - `net.rx`, `net.tx` in bytes per second over every interface except `lo`, and
+///   `net.IFACE.rx`, `net.IFACE.tx` per interface
+/// - `procs`: the processes using the most CPU (as many as the `procs` attribute, defaults to
+///   10), a list of rows with `pid`, `name`, `cpu` in percent and `mem` in bytes
+///
+/// Usage and rates are measured between ticks, the first tick reports usage since boot and no
+/// rates.
+pub struct SysInfo;
+
+#[derive(Default)]
+struct State {
+    /// `(busy, total)` jiffies for every line of `/proc/stat`, keyed by name
+    cpu: BTreeMap<String, (u64, u64)>,
+    /// `(rx, tx)` bytes per interface
+    net: BTreeMap<String, (u64, u64)>,
+    /// jiffies spent per process
+    procs: BTreeMap<u32, u64>,
+    /// jiffies that passed on all cores during the last tick
+    elapsed_jiffies: u64,
+    last: Option<Instant>,
+}
+
+impl Module for SysInfo {
+    fn subroutine(routine: &mut SubRoutine) {
+        let count = read_opt_attributes(routine.attributes.read().get("procs"))
+            .and_then(|p| p.parse().ok())
+            .unwrap_or(DEFAULT_PROCS);
+
+        let state = routine.state.get::<State>();
+        let mut out: BTreeMap<String, String> = BTreeMap::new();
+
+        cpu(state, &mut out);
+        memory(&mut out);
+        load(&mut out);
+        uptime(&mut out);
+        network(state, &mut out);
+        let procs = processes(state, count);
+        state.last = Some(Instant::now());
+
+        routine.publish(out);
+        routine.publish([("procs".to_string(), procs)]);
+    }
+}
+
+fn pct(part: f64, total: f64) -> String {
+    if total <= 0.0 {
+        return "0.0".to_string();
+    }
+    format!("{:.1}", part / total * 100.0)
+}
+
+fn cpu(state: &mut State, out: &mut BTreeMap<String, String>) {
+    let Ok(stat) = fs::read_to_string("/proc/stat") else {
+        return;
+    };
+
+    let mut cores = 0;
+    for line in stat.lines().filter(|l| l.starts_with("cpu")) {
+        let mut fields = line.split_whitespace();
+        let name = fields.next().unwrap_or_default().to_string();
+        let values: Vec<u64> = fields.filter_map(|f| f.parse().ok()).collect();
+        if values.len() < 4 {
+            continue;
+        }
+
+        // guest time is already counted in user time
+        let total: u64 = values.iter().take(8).sum();
+        let idle = values[3] + values.get(4).copied().unwrap_or(0);
+        let busy = total - idle;
+
+        let (prev_busy, prev_total) = state.cpu.get(&name).copied().unwrap_or((0, 0));
+        let usage = pct(
+            busy.saturating_sub(prev_busy) as f64,
+            total.saturating_sub(prev_total) as f64,
+        );
+        state.cpu.insert(name.clone(), (busy, total));
+        if name == "cpu" {
+            state.elapsed_jiffies = total.saturating_sub(prev_total);
+        }
+
+        // "cpu" is the total, "cpuN" are the cores
+        match name.strip_prefix("cpu").filter(|n| !n.is_empty()) {
+            Some(n) => {
+                cores += 1;
+                out.insert(format!("cpu.{n}"), usage);
+            }
+            None => {
+                out.insert("cpu".to_string(), usage);
+            }
+        }
+    }
+    out.insert("cpu.count".to_string(), cores.to_string());
+}
+
+fn memory(out: &mut BTreeMap<String, String>) {
+    let Ok(meminfo) = fs::read_to_string("/proc/meminfo") else {
+        return;
+    };
+
+    // every value is in kB
+    let info: BTreeMap<&str, u64> = meminfo
+        .lines()
+        .filter_map(|l| {
+            let (key, val) = l.split_once(':')?;
+            let kb = val.split_whitespace().next()?.parse::<u64>().ok()?;
+            Some((key, kb * 1024))
+        })
+        .collect();
+    let get = |k: &str| info.get(k).copied().unwrap_or(0);
+
+    let (total, available) = (get("MemTotal"), get("MemAvailable"));
+    let used = total.saturating_sub(available);
+    out.insert("mem.total".to_string(), total.to_string());
+    out.insert("mem.available".to_string(), available.to_string());
+    out.insert("mem.used".to_string(), used.to_string());
+    out.insert("mem.used_pct".to_string(), pct(used as f64, total as f64));
+
+    let (total, free) = (get("SwapTotal"), get("SwapFree"));
+    let used = total.saturating_sub(free);
+    out.insert("swap.total".to_string(), total.to_string());
+    out.insert("swap.used".to_string(), used.to_string());
+    out.insert("swap.used_pct".to_string(), pct(used as f64, total as f64));
+}
+
+fn load(out: &mut BTreeMap<String, String>) {
+    let Ok(loadavg) = fs::read_to_string("/proc/loadavg") else {
+        return;
+    };
+
+    for (key, val) in ["load.1", "load.5", "load.15"]
+        .iter()
+        .zip(loadavg.split_whitespace())
+    {
+        out.insert(key.to_string(), val.to_string());
+    }
+}
+
+fn uptime(out: &mut BTreeMap<String, String>) {
+    let Some(secs) = fs::read_to_string("/proc/uptime")
+        .ok()
+        .and_then(|u| u.split_whitespace().next()?.parse::<f64>().ok())
+    else {
+        return;
+    };
+
+    let secs = secs as u64;
+    let (days, hours, mins) = (secs / 86400, secs % 86400 / 3600, secs % 3600 / 60);
+    let human = if days > 0 {
+        format!("{days}d {hours}h {mins}m")
+    } else if hours > 0 {
+        format!("{hours}h {mins}m")
+    } else {
+        format!("{mins}m")
+    };
+
+    out.insert("uptime".to_string(), secs.to_string());
+    out.insert("uptime.human".to_string(), human);
+}
+
+fn network(state: &mut State, out: &mut BTreeMap<String, String>) {
+    let Ok(dev) = fs::read_to_string("/proc/net/dev") else {
+        return;
+    };
+    let elapsed = state.last.map(|l| l.elapsed().as_secs_f64());
+
+    let (mut rx_total, mut tx_total) = (0.0, 0.0);
+    // the first two lines are headers
+    for line in dev.lines().skip(2) {
+        let Some((iface, counters)) = line.split_once(':') else {
+            continue;
+        };
+        let iface = iface.trim().to_string();
+        let counters: Vec<u64> = counters
+            .split_whitespace()
+            .filter_map(|c| c.parse().ok())
+            .collect();
+        let (Some(&rx), Some(&tx)) = (counters.first(), counters.get(8)) else {
+            continue;
+        };
+
+        let prev = state.net.insert(iface.clone(), (rx, tx));
+        let (Some((prev_rx, prev_tx)), Some(elapsed)) = (prev, elapsed) else {
+            continue;
+        };
+        if elapsed <= 0.0 {
+            continue;
+        }
+
+        let rx_rate = rx.saturating_sub(prev_rx) as f64 / elapsed;
+        let tx_rate = tx.saturating_sub(prev_tx) as f64 / elapsed;
+        out.insert(format!("net.{iface}.rx"), format!("{rx_rate:.0}"));
+        out.insert(format!("net.{iface}.tx"), format!("{tx_rate:.0}"));
+
+        if iface != "lo" {
+            rx_total += rx_rate;
+            tx_total += tx_rate;
+        }
+    }
+
+    if elapsed.is_some() {
+        out.insert("net.rx".to_string(), format!("{rx_total:.0}"));
+        out.insert("net.tx".to_string(), format!("{tx_total:.0}"));
+    }
+}
+
+/// The `count` processes that used the most CPU since the last tick
+fn processes(state: &mut State, count: usize) -> Vec<Value> {
+    let Ok(entries) = fs::read_dir("/proc") else {
+        return vec![];
+    };
+    // SAFETY: sysconf has no preconditions
+    let page_size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) }.max(0) as u64;
+
+    let mut seen = BTreeMap::new();
+    let mut procs: Vec<(u32, String, f64, u64)> = entries
+        .filter_map(|e| {
+            let pid: u32 = e.ok()?.file_name().to_str()?.parse().ok()?;
+            let stat = fs::read_to_string(format!("/proc/{pid}/stat")).ok()?;
+
+            // the name is in parentheses and may contain spaces itself
+            let (name, rest) = stat.split_once(" (")?.1.rsplit_once(") ")?;
+            let fields: Vec<&str> = rest.split_whitespace().collect();
+            // state is field 3 of the file, utime 14, stime 15 and rss 24
+            let utime: u64 = fields.get(11)?.parse().ok()?;
+            let stime: u64 = fields.get(12)?.parse().ok()?;
+            let rss: u64 = fields.get(21)?.parse().ok()?;
+
+            let jiffies = utime + stime;
+            let prev = state.procs.get(&pid).copied().unwrap_or(0);
+            seen.insert(pid, jiffies);
+
+            let cpu = if state.elapsed_jiffies == 0 {
+                0.0
+            } else {
+                jiffies.saturating_sub(prev) as f64 / state.elapsed_jiffies as f64 * 100.0
+            };
+            Some((pid, name.to_string(), cpu, rss * page_size))
+        })
+        .collect();
+    // forget processes that exited
+    state.procs = seen;
+
+    procs.sort_by(|a, b| b.2.total_cmp(&a.2).then(b.3.cmp(&a.3)));
+    procs
+        .into_iter()
+        .take(count)
+        .map(|(pid, name, cpu, mem)| {
+            Value::from(BTreeMap::from([
+                ("pid".to_string(), Value::from(pid.to_string())),
+                ("name".to_string(), Value::from(name)),
+                ("cpu".to_string(), Value::from(format!("{cpu:.1}"))),
+                ("mem".to_string(), Value::from(mem.to_string())),
+            ]))
+        })
+        .collect()
+}
@@ -0,0 +1,100 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use tokio::{
+    sync::mpsc::UnboundedSender,
+    task::{self, JoinHandle},
+    time::{self, MissedTickBehavior},
+};
+
+use crate::backend::SubRoutine;
+
+/// Aligned ticks land this long after the boundary, so the routine never sees the time right
+/// before it
+const ALIGN_SLACK: Duration = Duration::from_millis(2);
+
+/// Drives every `SubRoutine` on the tokio runtime.
+///
+/// Each subroutine gets its own task which ticks at the subroutine's interval. Whenever a tick
+/// changes the contents of the store, the UI is notified through the `notify` channel so it can
+/// redraw. Dropping the scheduler stops all subroutines, including ticks that are still running.
+///
+/// Aligned subroutines tick on multiples of their interval on the wall clock (every full second,
+/// minute, ...) instead of counting from when they were started, so clocks don't drift.
+/// The first tick always happens right away.
+pub struct Scheduler {
+    handles: Vec<JoinHandle<()>>,
+    /// Aborting a task can't interrupt a blocking tick, these tell the routines to give up
+    stops: Vec<Arc<AtomicBool>>,
+}
+
+impl Scheduler {
+    pub fn start(subroutines: Vec<SubRoutine>, notify: UnboundedSender<()>) -> Self {
+        let stops = subroutines.iter().map(|sr| sr.stop_flag()).collect();
+        let handles = subroutines
+            .into_iter()
+            .map(|sr| tokio::spawn(Self::drive(sr, notify.clone())))
+            .collect();
+
+        Self { handles, stops }
+    }
+
+    async fn drive(mut sr: SubRoutine, notify: UnboundedSender<()>) {
+        let mut ticker = time::interval(sr.interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+        let mut first = true;
+
+        loop {
+            if sr.align && !first {
+                time::sleep(until_boundary(sr.interval)).await;
+            } else {
+                ticker.tick().await;
+            }
+            first = false;
+
+            // routines are synchronous and allowed to block (reading files, running commands...)
+            let Ok((ret, changed)) = task::spawn_blocking(move || {
+                let changed = sr.tick();
+                (sr, changed)
+            })
+            .await
+            else {
+                // the routine panicked, nothing more we can do for it
+                return;
+            };
+            sr = ret;
+
+            // the receiver is gone when the UI has exited
+            if changed && notify.send(()).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Time left until the next multiple of `interval` since the epoch
+fn until_boundary(interval: Duration) -> Duration {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let interval_ns = interval.as_nanos().max(1);
+    let left = interval_ns - now % interval_ns;
+
+    Duration::from_nanos(left as u64) + ALIGN_SLACK
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.stops
+            .iter()
+            .for_each(|s| s.store(true, Ordering::Relaxed));
+        self.handles.iter().for_each(|h| h.abort());
+    }
+}
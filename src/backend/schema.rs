@@ -0,0 +1,803 @@
+//! Description of every element and attribute type
+//!
+//! Both the validator and `rpanel --xsd` (which generates `rpanel.xsd`) are built from this, so
+//! the two can't drift apart.
+
+use std::{fmt::Write, str::FromStr};
+
+use crate::{
+    backend::{
+        plugins::{check_format, parse_formats},
+        theme::{ThemeColor, parse_palette},
+        xmlparser::size_from_str,
+    },
+    utils::{List, Sides, Thresholds, duration_from_str},
+};
+
+const BORDER_TYPES: &[&str] = &["rounded", "double", "thick", "ultrathick", "plain"];
+const FLEXES: &[&str] = &["end", "center", "spaceBetween", "spaceAround", "start"];
+const MISSING: &[&str] = &["blank", "placeholder", "error"];
+const ALIGNS: &[&str] = &["left", "center", "right"];
+const VALIGNS: &[&str] = &["top", "center", "bottom"];
+const TITLE_POSITIONS: &[&str] = &["top", "bottom"];
+const WRAPS: &[&str] = &["true", "false", "trim"];
+const COLORS: &[&str] = &[
+    "black",
+    "red",
+    "green",
+    "yellow",
+    "blue",
+    "magenta",
+    "cyan",
+    "gray",
+    "darkgray",
+    "lightred",
+    "lightgreen",
+    "lightyellow",
+    "lightblue",
+    "lightmagenta",
+    "lightcyan",
+    "white",
+];
+
+/// Pattern for a number from 0 to 255
+const BYTE: &str = "0*(1?[0-9]{1,2}|2[0-4][0-9]|25[0-5])";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttrType {
+    String,
+    Bool,
+    /// Up to 65535
+    NonNegativeInteger,
+    Number,
+    Border,
+    BorderType,
+    Flex,
+    Align,
+    /// `;` separated, one for every title
+    TitleAligns,
+    TitlePositions,
+    VAlign,
+    Wrap,
+    Padding,
+    Size,
+    SizeList,
+    Color,
+    ColorList,
+    Interval,
+    Missing,
+    Palette,
+    TimeZone,
+    Strftime,
+    Formats,
+    Thresholds,
+}
+
+/// Every type that needs its own `simpleType` in the XSD
+const SIMPLE_TYPES: &[AttrType] = &[
+    AttrType::BorderType,
+    AttrType::Border,
+    AttrType::Flex,
+    AttrType::Align,
+    AttrType::TitleAligns,
+    AttrType::TitlePositions,
+    AttrType::VAlign,
+    AttrType::Wrap,
+    AttrType::Padding,
+    AttrType::Size,
+    AttrType::SizeList,
+    AttrType::Interval,
+    AttrType::Missing,
+    AttrType::Color,
+    AttrType::ColorList,
+    AttrType::Palette,
+    AttrType::TimeZone,
+    AttrType::Strftime,
+    AttrType::Formats,
+    AttrType::Thresholds,
+];
+
+impl AttrType {
+    pub fn check(&self, v: &str) -> Result<(), String> {
+        match self {
+            AttrType::String => Ok(()),
+            AttrType::Bool => one_of(v, &["true", "false", "1", "0"]),
+            AttrType::NonNegativeInteger => v.parse::<u16>().map(|_| ()).map_err(|e| e.to_string()),
+            AttrType::Number => match v.parse::<f64>() {
+                Ok(n) if n.is_finite() => Ok(()),
+                _ => Err("not a number".to_string()),
+            },
+            AttrType::Border => {
+                if v == "all"
+                    || v == "none"
+                    || (!v.is_empty() && v.chars().all(|c| "rlbt".contains(c)))
+                {
+                    Ok(())
+                } else {
+                    Err("should be all, none or a combination of r, l, b and t".to_string())
+                }
+            }
+            AttrType::BorderType => one_of(v, BORDER_TYPES),
+            AttrType::Flex => one_of(v, FLEXES),
+            AttrType::Align => one_of(v, ALIGNS),
+            AttrType::TitleAligns => v.split(';').try_for_each(|a| one_of(a.trim(), ALIGNS)),
+            AttrType::TitlePositions => v
+                .split(';')
+                .try_for_each(|p| one_of(p.trim(), TITLE_POSITIONS)),
+            AttrType::VAlign => one_of(v, VALIGNS),
+            AttrType::Wrap => one_of(v, WRAPS),
+            AttrType::Missing => one_of(v, MISSING),
+            AttrType::Padding => Sides::from_str(v).map(|_| ()).map_err(|e| e.to_string()),
+            AttrType::Palette => parse_palette(v).map(|_| ()),
+            AttrType::Size => size_from_str(v).map(|_| ()).map_err(|e| e.to_string()),
+            AttrType::SizeList => v
+                .split(',')
+                .try_for_each(|s| size_from_str(s.trim()).map(|_| ()))
+                .map_err(|e| e.to_string()),
+            AttrType::Color => ThemeColor::from_str(v).map(|_| ()),
+            AttrType::ColorList => List::<ThemeColor>::from_str(v)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            AttrType::Interval => duration_from_str(v).map(|_| ()).map_err(|e| e.to_string()),
+            AttrType::TimeZone => jiff::tz::TimeZone::get(v)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+            AttrType::Strftime => check_format(v),
+            AttrType::Formats => parse_formats(v)
+                .iter()
+                .try_for_each(|(_, f)| check_format(f)),
+            AttrType::Thresholds => Thresholds::from_str(v)
+                .map(|_| ())
+                .map_err(|e| e.to_string()),
+        }
+    }
+
+    fn xsd_name(&self) -> &'static str {
+        match self {
+            AttrType::String => "xs:string",
+            AttrType::Bool => "xs:boolean",
+            AttrType::NonNegativeInteger => "xs:unsignedShort",
+            AttrType::Number => "xs:double",
+            AttrType::Border => "Border",
+            AttrType::BorderType => "BorderType",
+            AttrType::Flex => "Flex",
+            AttrType::Align => "Align",
+            AttrType::TitleAligns => "TitleAligns",
+            AttrType::TitlePositions => "TitlePositions",
+            AttrType::VAlign => "VAlign",
+            AttrType::Wrap => "Wrap",
+            AttrType::Padding => "Padding",
+            AttrType::Size => "Size",
+            AttrType::SizeList => "SizeList",
+            AttrType::Color => "Color",
+            AttrType::ColorList => "ColorList",
+            AttrType::Interval => "Interval",
+            AttrType::Missing => "Missing",
+            AttrType::Palette => "Palette",
+            AttrType::TimeZone => "TimeZone",
+            AttrType::Strftime => "Strftime",
+            AttrType::Formats => "Formats",
+            AttrType::Thresholds => "Thresholds",
+        }
+    }
+
+    /// Everything inside the `simpleType` element
+    fn xsd_body(&self) -> String {
+        match self {
+            AttrType::String | AttrType::Bool | AttrType::NonNegativeInteger | AttrType::Number => {
+                String::new()
+            }
+            AttrType::BorderType => enumeration(
+                Some("Enum defined as: rounded, double, thick, ultrathick or plain"),
+                BORDER_TYPES,
+            ),
+            AttrType::Flex => enumeration(
+                Some("Enum defined as: end, center, spaceBetween, spaceAround or start"),
+                FLEXES,
+            ),
+            AttrType::Align => enumeration(Some("Enum defined as: left, center or right"), ALIGNS),
+            AttrType::VAlign => {
+                enumeration(Some("Enum defined as: top, center or bottom"), VALIGNS)
+            }
+            AttrType::TitleAligns => {
+                documentation(
+                    "Alignment of every title, separated by ;. Defined as: left, center or right",
+                ) + r#"        <xs:restriction base="xs:string">
+            <xs:pattern value="(left|center|right)( *; *(left|center|right))*" />
+        </xs:restriction>
+"#
+            }
+            AttrType::TitlePositions => {
+                documentation("Position of every title, separated by ;. Defined as: top or bottom")
+                    + r#"        <xs:restriction base="xs:string">
+            <xs:pattern value="(top|bottom)( *; *(top|bottom))*" />
+        </xs:restriction>
+"#
+            }
+            AttrType::Wrap => enumeration(
+                Some("Enum defined as: true, false or trim (wraps and strips leading whitespace)"),
+                WRAPS,
+            ),
+            AttrType::Missing => enumeration(
+                Some("Enum defined as: blank, placeholder or error"),
+                MISSING,
+            ),
+            AttrType::Color => {
+                let mut res = documentation(
+                    "A color name, #rrggbb, rgb(r, g, b), a 256 color index or @name from the palette",
+                );
+                res.push_str("        <xs:union>\n            <xs:simpleType>\n");
+                for line in enumeration(None, COLORS).lines() {
+                    let _ = writeln!(res, "        {line}");
+                }
+                res.push_str(&
+                    r##"            </xs:simpleType>
+            <xs:simpleType>
+                <xs:restriction base="xs:string">
+                    <xs:pattern value="#[0-9a-fA-F]{6}|rgb\( *{BYTE} *, *{BYTE} *, *{BYTE} *\)|{BYTE}|@\S+" />
+                </xs:restriction>
+            </xs:simpleType>
+        </xs:union>
+"##
+                    .replace("{BYTE}", BYTE),
+                );
+                res
+            }
+            AttrType::Palette => {
+                documentation("Named colors, used as @name. Defined as: name=color;name=color")
+                    + "        <xs:restriction base=\"xs:string\" />\n"
+            }
+            AttrType::Border => {
+                documentation(
+                    "Enum defined as: all, none or a combination of rltb (right, left, top, bottom)",
+                ) + r#"        <xs:union>
+            <xs:simpleType>
+                <xs:restriction base="xs:string">
+                    <xs:enumeration value="none" />
+                    <xs:enumeration value="all" />
+                </xs:restriction>
+            </xs:simpleType>
+            <xs:simpleType>
+                <xs:restriction base="xs:string">
+                    <xs:pattern value="[rlbt]{1,4}" />
+                </xs:restriction>
+            </xs:simpleType>
+        </xs:union>
+"#
+            }
+            AttrType::Padding => {
+                documentation(
+                    "Space on each side, like CSS. Defined as: | all | vertical horizontal \
+                     | top horizontal bottom | top right bottom left",
+                ) + r#"        <xs:restriction base="xs:string">
+            <xs:pattern value=" *[0-9]+( +[0-9]+){0,3} *" />
+        </xs:restriction>
+"#
+            }
+            AttrType::Size => {
+                documentation(
+                    "Size of an element. Defined as: | X% == Percentage(X) | X/Y == Ratios(X,Y) \
+                     | X/ == Fill(X) | X == Length(X) | _ == Fill(1) | min:X == Min(X) \
+                     | max:X == Max(X)",
+                ) + "        <xs:restriction base=\"xs:string\" />\n"
+            }
+            AttrType::SizeList => {
+                documentation("Comma separated sizes, e.g. 6,_,10%")
+                    + "        <xs:restriction base=\"xs:string\" />\n"
+            }
+            AttrType::TimeZone => {
+                documentation("IANA time zone, e.g. Europe/Stockholm")
+                    + "        <xs:restriction base=\"xs:string\" />\n"
+            }
+            AttrType::Strftime => {
+                documentation("strftime format, e.g. %H:%M:%S")
+                    + "        <xs:restriction base=\"xs:string\" />\n"
+            }
+            AttrType::Formats => {
+                documentation("Named strftime formats. Defined as: name=format;name=format")
+                    + "        <xs:restriction base=\"xs:string\" />\n"
+            }
+            AttrType::ColorList => {
+                documentation("Comma separated colors, e.g. green,red")
+                    + "        <xs:restriction base=\"xs:string\" />\n"
+            }
+            AttrType::Thresholds => {
+                documentation(
+                    "Colours picked by how full a gauge is. Defined as: percent=color,percent=color",
+                ) + "        <xs:restriction base=\"xs:string\" />\n"
+            }
+            AttrType::Interval => {
+                documentation(
+                    "Interval between subroutine ticks. Defined as: | Xms | Xs | Xm | Xh | X == Xs",
+                ) + r#"        <xs:restriction base="xs:string">
+            <xs:pattern value="[0-9]+(\.[0-9]+)?(ms|s|m|h)?" />
+        </xs:restriction>
+"#
+            }
+        }
+    }
+}
+
+fn one_of(v: &str, allowed: &[&str]) -> Result<(), String> {
+    if allowed.contains(&v) {
+        Ok(())
+    } else {
+        Err(format!("should be one of {}", allowed.join(", ")))
+    }
+}
+
+fn documentation(doc: &str) -> String {
+    format!(
+        "        <xs:annotation>\n            <xs:documentation>{doc}</xs:documentation>\n        </xs:annotation>\n"
+    )
+}
+
+fn enumeration(doc: Option<&str>, values: &[&str]) -> String {
+    let mut res = doc.map(documentation).unwrap_or_default();
+    res.push_str("        <xs:restriction base=\"xs:string\">\n");
+    for v in values {
+        let _ = writeln!(res, "            <xs:enumeration value=\"{v}\" />");
+    }
+    res.push_str("        </xs:restriction>\n");
+    res
+}
+
+pub struct AttrDef {
+    pub name: &'static str,
+    pub ty: AttrType,
+    pub required: bool,
+    /// False for attributes read once while the layout is loaded, before any plugin has run
+    pub templated: bool,
+}
+
+impl AttrDef {
+    const fn fixed(mut self) -> Self {
+        self.templated = false;
+        self
+    }
+}
+
+const fn attr(name: &'static str, ty: AttrType) -> AttrDef {
+    AttrDef {
+        name,
+        ty,
+        required: false,
+        templated: true,
+    }
+}
+
+const fn required(name: &'static str, ty: AttrType) -> AttrDef {
+    AttrDef {
+        name,
+        ty,
+        required: true,
+        templated: true,
+    }
+}
+
+/// How an element relates to the rest of the layout
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ElementKind {
+    /// `<window>`
+    Root,
+    /// Container with the layout attributes
+    Layout,
+    /// Layout that owns a store and a subroutine
+    Plugin,
+    /// Text content and markup
+    Text,
+    /// Markup that styles the text inside `<text>`
+    Inline,
+    /// No children at all
+    Empty,
+    /// `<style>`, a stylesheet instead of something to render
+    Style,
+}
+
+pub struct ElementDef {
+    pub tag: &'static str,
+    pub kind: ElementKind,
+    /// Attributes on top of the ones every element of this kind has
+    pub attrs: &'static [AttrDef],
+}
+
+/// Taken by every element, for stylesheet selectors
+const COMMON_ATTRS: &[AttrDef] = &[
+    attr("class", AttrType::String).fixed(),
+    attr("id", AttrType::String).fixed(),
+];
+
+/// Taken by everything that is laid out inside a layout
+const WIDGET_ATTRS: &[AttrDef] = &[attr("size", AttrType::Size).fixed()];
+
+const LAYOUT_ATTRS: &[AttrDef] = &[
+    attr("border", AttrType::Border),
+    attr("borderType", AttrType::BorderType),
+    attr("borderColor", AttrType::Color),
+    attr("title", AttrType::String),
+    attr("titleAlign", AttrType::TitleAligns),
+    attr("titlePosition", AttrType::TitlePositions),
+    attr("flex", AttrType::Flex),
+    attr("bg", AttrType::Color),
+    attr("fg", AttrType::Color),
+    attr("padding", AttrType::Padding),
+    attr("spacing", AttrType::NonNegativeInteger),
+];
+
+const PLUGIN_ATTRS: &[AttrDef] = &[
+    attr("interval", AttrType::Interval).fixed(),
+    attr("alignTicks", AttrType::Bool).fixed(),
+];
+
+pub const ELEMENTS: &[ElementDef] = &[
+    ElementDef {
+        tag: "window",
+        kind: ElementKind::Root,
+        attrs: &[
+            attr("missing", AttrType::Missing).fixed(),
+            attr("missingText", AttrType::String).fixed(),
+            attr("palette", AttrType::Palette).fixed(),
+        ],
+    },
+    ElementDef {
+        tag: "style",
+        kind: ElementKind::Style,
+        attrs: &[attr("src", AttrType::String).fixed()],
+    },
+    ElementDef {
+        tag: "row",
+        kind: ElementKind::Layout,
+        attrs: &[],
+    },
+    ElementDef {
+        tag: "column",
+        kind: ElementKind::Layout,
+        attrs: &[],
+    },
+    ElementDef {
+        tag: "text",
+        kind: ElementKind::Text,
+        attrs: &[
+            attr("align", AttrType::Align),
+            attr("valign", AttrType::VAlign),
+            attr("wrap", AttrType::Wrap),
+            attr("ellipsis", AttrType::String),
+        ],
+    },
+    ElementDef {
+        tag: "b",
+        kind: ElementKind::Inline,
+        attrs: &[],
+    },
+    ElementDef {
+        tag: "i",
+        kind: ElementKind::Inline,
+        attrs: &[],
+    },
+    ElementDef {
+        tag: "u",
+        kind: ElementKind::Inline,
+        attrs: &[],
+    },
+    ElementDef {
+        tag: "dim",
+        kind: ElementKind::Inline,
+        attrs: &[],
+    },
+    ElementDef {
+        tag: "reversed",
+        kind: ElementKind::Inline,
+        attrs: &[],
+    },
+    ElementDef {
+        tag: "span",
+        kind: ElementKind::Inline,
+        attrs: &[attr("fg", AttrType::Color), attr("bg", AttrType::Color)],
+    },
+    ElementDef {
+        tag: "block",
+        kind: ElementKind::Empty,
+        attrs: &[attr("fill", AttrType::Color)],
+    },
+    ElementDef {
+        tag: "gauge",
+        kind: ElementKind::Empty,
+        attrs: &[
+            required("value", AttrType::Number),
+            attr("max", AttrType::Number),
+            attr("label", AttrType::String),
+            attr("fill", AttrType::Color),
+            attr("thresholds", AttrType::Thresholds),
+            attr("line", AttrType::Bool),
+        ],
+    },
+    ElementDef {
+        tag: "sparkline",
+        kind: ElementKind::Empty,
+        attrs: &[
+            required("source", AttrType::String).fixed(),
+            attr("samples", AttrType::NonNegativeInteger).fixed(),
+            attr("max", AttrType::Number),
+            attr("fill", AttrType::Color),
+        ],
+    },
+    ElementDef {
+        tag: "chart",
+        kind: ElementKind::Empty,
+        attrs: &[
+            required("sources", AttrType::String).fixed(),
+            attr("labels", AttrType::String),
+            attr("colors", AttrType::ColorList),
+            attr("samples", AttrType::NonNegativeInteger).fixed(),
+            attr("min", AttrType::Number),
+            attr("max", AttrType::Number),
+            attr("xTitle", AttrType::String),
+            attr("yTitle", AttrType::String),
+            attr("legend", AttrType::Bool),
+        ],
+    },
+    ElementDef {
+        tag: "barchart",
+        kind: ElementKind::Empty,
+        attrs: &[
+            required("sources", AttrType::String).fixed(),
+            attr("labels", AttrType::String),
+            attr("colors", AttrType::ColorList),
+            attr("max", AttrType::Number),
+            attr("barWidth", AttrType::NonNegativeInteger),
+        ],
+    },
+    ElementDef {
+        tag: "table",
+        kind: ElementKind::Empty,
+        attrs: &[
+            required("source", AttrType::String),
+            attr("columns", AttrType::String),
+            attr("titles", AttrType::String),
+            attr("widths", AttrType::SizeList),
+            attr("header", AttrType::Bool),
+        ],
+    },
+    ElementDef {
+        tag: "list",
+        kind: ElementKind::Empty,
+        attrs: &[required("source", AttrType::String)],
+    },
+    ElementDef {
+        tag: "exec",
+        kind: ElementKind::Plugin,
+        attrs: &[
+            required("cmd", AttrType::String),
+            attr("timeout", AttrType::Interval),
+        ],
+    },
+    ElementDef {
+        tag: "sysinfo",
+        kind: ElementKind::Plugin,
+        attrs: &[attr("procs", AttrType::NonNegativeInteger)],
+    },
+    ElementDef {
+        tag: "battery",
+        kind: ElementKind::Plugin,
+        attrs: &[attr("path", AttrType::String)],
+    },
+    ElementDef {
+        tag: "disk",
+        kind: ElementKind::Plugin,
+        attrs: &[attr("mounts", AttrType::String)],
+    },
+    ElementDef {
+        tag: "clock",
+        kind: ElementKind::Plugin,
+        attrs: &[
+            attr("format", AttrType::Strftime),
+            attr("formats", AttrType::Formats),
+            attr("tz", AttrType::TimeZone),
+        ],
+    },
+];
+
+/// True if any element takes an attribute called `name`
+pub fn is_known_attr(name: &str) -> bool {
+    ELEMENTS
+        .iter()
+        .any(|e| e.all_attrs().any(|a| a.name == name))
+}
+
+impl ElementDef {
+    pub fn find(tag: &str) -> Option<&'static ElementDef> {
+        ELEMENTS.iter().find(|e| e.tag == tag)
+    }
+
+    /// Every attribute the element takes, including the ones of its kind
+    pub fn all_attrs(&self) -> impl Iterator<Item = &'static AttrDef> + use<> {
+        let inherited: &[&[AttrDef]] = match self.kind {
+            ElementKind::Layout => &[COMMON_ATTRS, WIDGET_ATTRS, LAYOUT_ATTRS],
+            ElementKind::Plugin => &[COMMON_ATTRS, WIDGET_ATTRS, LAYOUT_ATTRS, PLUGIN_ATTRS],
+            ElementKind::Text | ElementKind::Empty => &[COMMON_ATTRS, WIDGET_ATTRS],
+            ElementKind::Style => &[],
+            ElementKind::Root | ElementKind::Inline => &[COMMON_ATTRS],
+        };
+
+        inherited
+            .iter()
+            .flat_map(|a| a.iter())
+            .chain(self.attrs.iter())
+    }
+}
+
+fn xsd_attrs(out: &mut String, attrs: &[AttrDef], indent: usize) {
+    for a in attrs {
+        let required = if a.required { " use=\"required\"" } else { "" };
+        let _ = writeln!(
+            out,
+            "{:indent$}<xs:attribute name=\"{}\" type=\"{}\"{required} />",
+            "",
+            a.name,
+            a.ty.xsd_name()
+        );
+    }
+}
+
+const ANY_CHILDREN: &str = "<xs:sequence>
+                <xs:any minOccurs=\"0\" maxOccurs=\"unbounded\" processContents=\"lax\"></xs:any>
+            </xs:sequence>";
+
+fn xsd_element(out: &mut String, e: &ElementDef) {
+    let base = match e.kind {
+        ElementKind::Layout => "Layout",
+        ElementKind::Plugin => "Plugin",
+        ElementKind::Inline if e.attrs.is_empty() => {
+            let _ = writeln!(out, "    <xs:element name=\"{}\" type=\"Markup\" />", e.tag);
+            return;
+        }
+        ElementKind::Text | ElementKind::Inline => "Markup",
+        ElementKind::Root | ElementKind::Empty => {
+            let _ = writeln!(out, "    <xs:element name=\"{}\">", e.tag);
+            out.push_str("        <xs:complexType>\n");
+            if e.kind == ElementKind::Root {
+                let _ = writeln!(out, "            {ANY_CHILDREN}");
+            }
+            xsd_attrs(out, COMMON_ATTRS, 12);
+            if e.kind == ElementKind::Empty {
+                xsd_attrs(out, WIDGET_ATTRS, 12);
+            }
+            xsd_attrs(out, e.attrs, 12);
+            out.push_str("        </xs:complexType>\n    </xs:element>\n");
+            return;
+        }
+        ElementKind::Style => {
+            let _ = writeln!(out, "    <xs:element name=\"{}\">", e.tag);
+            out.push_str("        <xs:complexType>\n            <xs:simpleContent>\n                <xs:extension base=\"xs:string\">\n");
+            xsd_attrs(out, e.attrs, 20);
+            out.push_str("                </xs:extension>\n            </xs:simpleContent>\n        </xs:complexType>\n    </xs:element>\n");
+            return;
+        }
+    };
+
+    // the markup type is shared with inline elements, which aren't laid out
+    let widget_attrs: &[AttrDef] = if e.kind == ElementKind::Text {
+        WIDGET_ATTRS
+    } else {
+        &[]
+    };
+
+    if e.attrs.is_empty() && widget_attrs.is_empty() {
+        let _ = writeln!(out, "    <xs:element name=\"{}\" type=\"{base}\" />", e.tag);
+        return;
+    }
+
+    // extensions of mixed content have to be mixed themselves
+    let mixed = if base == "Markup" {
+        " mixed=\"true\""
+    } else {
+        ""
+    };
+    let _ = writeln!(out, "    <xs:element name=\"{}\">", e.tag);
+    let _ = writeln!(
+        out,
+        "        <xs:complexType{mixed}>\n            <xs:complexContent>\n                <xs:extension base=\"{base}\">"
+    );
+    xsd_attrs(out, widget_attrs, 20);
+    xsd_attrs(out, e.attrs, 20);
+    out.push_str("                </xs:extension>\n            </xs:complexContent>\n        </xs:complexType>\n    </xs:element>\n");
+}
+
+/// Generate `rpanel.xsd`
+pub fn xsd() -> String {
+    let mut out = String::from(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!-- Generated by rpanel (see the xsd option), do not edit by hand -->
+<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"
+    elementFormDefault="qualified"
+    attributeFormDefault="unqualified">
+
+    <!--              ELEMENTS              -->
+
+"#,
+    );
+
+    for e in ELEMENTS {
+        xsd_element(&mut out, e);
+    }
+
+    out.push_str("\n    <!--              TYPES              -->\n\n");
+
+    out.push_str("    <xs:complexType name=\"Layout\">\n");
+    let _ = writeln!(out, "        {}", ANY_CHILDREN.replace("\n    ", "\n"));
+    xsd_attrs(&mut out, COMMON_ATTRS, 8);
+    xsd_attrs(&mut out, WIDGET_ATTRS, 8);
+    xsd_attrs(&mut out, LAYOUT_ATTRS, 8);
+    out.push_str("    </xs:complexType>\n\n");
+
+    out.push_str(
+        r#"    <xs:complexType name="Plugin">
+        <xs:annotation>
+            <xs:documentation>Any unknown element is a plugin, which is laid out like a column</xs:documentation>
+        </xs:annotation>
+        <xs:complexContent>
+            <xs:extension base="Layout">
+"#,
+    );
+    xsd_attrs(&mut out, PLUGIN_ATTRS, 16);
+    out.push_str(
+        "                <xs:anyAttribute processContents=\"lax\" />\n            </xs:extension>\n        </xs:complexContent>\n    </xs:complexType>\n",
+    );
+
+    out.push_str(
+        r#"
+    <xs:complexType name="Markup" mixed="true">
+        <xs:annotation>
+            <xs:documentation>Text, which may be styled with the markup elements</xs:documentation>
+        </xs:annotation>
+        <xs:choice minOccurs="0" maxOccurs="unbounded">
+"#,
+    );
+    for e in ELEMENTS.iter().filter(|e| e.kind == ElementKind::Inline) {
+        let _ = writeln!(out, "            <xs:element ref=\"{}\" />", e.tag);
+    }
+    out.push_str("        </xs:choice>\n");
+    xsd_attrs(&mut out, COMMON_ATTRS, 8);
+    out.push_str("    </xs:complexType>\n");
+
+    for ty in SIMPLE_TYPES {
+        let _ = write!(
+            out,
+            "\n    <xs:simpleType name=\"{}\">\n{}    </xs:simpleType>\n",
+            ty.xsd_name(),
+            ty.xsd_body()
+        );
+    }
+
+    out.push_str("</xs:schema>\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn xsd_is_up_to_date() {
+        // regenerate with `rpanel --xsd > rpanel.xsd`
+        assert_eq!(xsd(), include_str!("../../rpanel.xsd"));
+    }
+
+    #[test]
+    fn types_match_the_xsd() {
+        for v in ["true", "false", "1", "0"] {
+            assert!(AttrType::Bool.check(v).is_ok(), "{v}");
+        }
+        assert!(AttrType::Bool.check("yes").is_err());
+
+        assert!(AttrType::NonNegativeInteger.check("65535").is_ok());
+        assert!(AttrType::NonNegativeInteger.check("65536").is_err());
+
+        for v in ["255", "0", "rgb(255, 0, 12)"] {
+            assert!(AttrType::Color.check(v).is_ok(), "{v}");
+        }
+        for v in ["256", "999", "rgb(256, 0, 0)"] {
+            assert!(AttrType::Color.check(v).is_err(), "{v}");
+        }
+    }
+}
@@ -0,0 +1,488 @@
+//! Stylesheets, which give elements default attributes
+//!
+//! ```css
+//! column.card { border: all; borderType: rounded }
+//! #cpu text, .warn { align: right }
+//! ```
+//!
+//! Like CSS, the most specific selector wins (ids over classes over tags) and later rules win over
+//! earlier ones of the same specificity. Attributes written on the element itself win over both.
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use roxmltree::{Document, Node, TextPos};
+
+use crate::backend::{
+    schema::{self, ElementDef},
+    template::unquote,
+    validator::Diagnostic,
+};
+
+/// Element holding a stylesheet, only allowed directly inside `<window>`
+pub const TAG: &str = "style";
+
+#[derive(Default)]
+pub struct Stylesheet {
+    rules: Vec<Rule>,
+    /// External stylesheets that were read, so they can be watched
+    pub files: Vec<PathBuf>,
+}
+
+struct Rule {
+    selector: Selector,
+    declarations: Vec<(String, String)>,
+}
+
+/// Compound selectors separated by spaces, the last one has to match the element itself
+struct Selector(Vec<Compound>);
+
+/// `tag.class#id`, every part is optional
+#[derive(Default)]
+struct Compound {
+    tag: Option<String>,
+    id: Option<String>,
+    classes: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct ParseError {
+    /// Line in the stylesheet, starting at 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl Stylesheet {
+    /// Read every `<style>` inside `<window>`, in document order
+    ///
+    /// External stylesheets given by `src` are relative to `dir`. Problems are returned instead of
+    /// failing, the rules that could be read are kept.
+    pub fn load(doc: &Document, dir: &Path) -> (Self, Vec<Diagnostic>) {
+        let mut sheet = Self::default();
+        let mut diagnostics = vec![];
+
+        for style in doc
+            .root_element()
+            .children()
+            .filter(|c| c.has_tag_name(TAG))
+        {
+            let pos = doc.text_pos_at(style.range().start);
+
+            if let Some(src) = style.attribute("src") {
+                let path = dir.join(src);
+                sheet.files.push(path.clone());
+
+                let parsed = match fs::read_to_string(&path) {
+                    Ok(contents) => Self::parse(&contents),
+                    Err(e) => {
+                        diagnostics.push(Diagnostic {
+                            pos,
+                            message: format!("Failed to read stylesheet {}: {e}", path.display()),
+                        });
+                        continue;
+                    }
+                };
+                match parsed {
+                    Ok(s) => sheet.rules.extend(s.rules),
+                    Err(e) => diagnostics.push(Diagnostic {
+                        pos,
+                        message: format!("{}:{}: {}", path.display(), e.line, e.message),
+                    }),
+                }
+                continue;
+            }
+
+            match Self::parse(style.text().unwrap_or_default()) {
+                Ok(s) => sheet.rules.extend(s.rules),
+                Err(e) => {
+                    // point at the line inside the layout
+                    let start = style
+                        .first_child()
+                        .map(|t| doc.text_pos_at(t.range().start))
+                        .unwrap_or(pos);
+                    let pos = if e.line == 1 {
+                        start
+                    } else {
+                        TextPos::new(start.row + e.line as u32 - 1, 1)
+                    };
+                    diagnostics.push(Diagnostic {
+                        pos,
+                        message: format!("Invalid stylesheet: {}", e.message),
+                    });
+                }
+            }
+        }
+
+        (sheet, diagnostics)
+    }
+
+    pub fn parse(src: &str) -> Result<Self, ParseError> {
+        let src = strip_comments(src);
+        let error = |offset: usize, message: String| ParseError {
+            line: src[..offset].matches('\n').count() + 1,
+            message,
+        };
+
+        let mut rules = vec![];
+        let mut pos = 0;
+        while let Some(start) = src[pos..]
+            .find(|c: char| !c.is_whitespace())
+            .map(|i| pos + i)
+        {
+            let open = src[start..]
+                .find('{')
+                .map(|i| start + i)
+                .ok_or_else(|| error(start, "expected { after the selector".to_string()))?;
+            let close = block_end(&src, open + 1)
+                .ok_or_else(|| error(open, "{ is never closed".to_string()))?;
+            let declarations = parse_declarations(&src[open + 1..close])
+                .map_err(|(i, e)| error(open + 1 + i, e))?;
+
+            for s in src[start..open].split(',') {
+                let selector = Selector::parse(s).map_err(|e| error(start, e))?;
+                rules.push(Rule {
+                    selector,
+                    declarations: declarations.clone(),
+                });
+            }
+            pos = close + 1;
+        }
+
+        Ok(Self {
+            rules,
+            files: vec![],
+        })
+    }
+
+    /// Attributes the stylesheet gives `node`, after the cascade
+    ///
+    /// Attributes the element doesn't take are left out, unknown elements (plugins) take any.
+    pub fn defaults(&self, node: Node) -> BTreeMap<String, String> {
+        let mut matching: Vec<&Rule> = self
+            .rules
+            .iter()
+            .filter(|r| r.selector.matches(node))
+            .collect();
+        // stable, so rules of the same specificity stay in order
+        matching.sort_by_key(|r| r.selector.specificity());
+
+        let def = ElementDef::find(node.tag_name().name());
+        let mut res = BTreeMap::new();
+        for (name, value) in matching.iter().flat_map(|r| r.declarations.iter()) {
+            if def.is_none_or(|d| d.all_attrs().any(|a| a.name == name)) {
+                res.insert(name.clone(), value.clone());
+            }
+        }
+        res
+    }
+
+    /// `name` as written on `node`, or else as given by the stylesheet
+    pub fn attribute(&self, node: Node, name: &str) -> Option<String> {
+        node.attribute(name)
+            .map(str::to_string)
+            .or_else(|| self.defaults(node).remove(name))
+    }
+}
+
+impl Selector {
+    fn parse(s: &str) -> Result<Self, String> {
+        let compounds = s
+            .split_whitespace()
+            .map(Compound::parse)
+            .collect::<Result<Vec<_>, _>>()?;
+        if compounds.is_empty() {
+            return Err("empty selector".to_string());
+        }
+        Ok(Self(compounds))
+    }
+
+    fn matches(&self, node: Node) -> bool {
+        let Some((subject, ancestors)) = self.0.split_last() else {
+            return false;
+        };
+        if !subject.matches(node) {
+            return false;
+        }
+
+        // every compound has to match an ancestor, closest first
+        let mut up = node.ancestors().skip(1).filter(|n| n.is_element());
+        ancestors.iter().rev().all(|c| up.any(|n| c.matches(n)))
+    }
+
+    /// Ids, classes and tags
+    fn specificity(&self) -> (usize, usize, usize) {
+        self.0.iter().fold((0, 0, 0), |(ids, classes, tags), c| {
+            (
+                ids + c.id.is_some() as usize,
+                classes + c.classes.len(),
+                tags + c.tag.is_some() as usize,
+            )
+        })
+    }
+}
+
+impl Compound {
+    fn parse(s: &str) -> Result<Self, String> {
+        let invalid = || format!("invalid selector \"{s}\"");
+        let mut compound = Self::default();
+
+        let mut rest = match s.strip_prefix('*') {
+            Some(rest) => rest,
+            None => {
+                let (tag, rest) = ident(s);
+                if !tag.is_empty() {
+                    compound.tag = Some(tag.to_string());
+                }
+                rest
+            }
+        };
+
+        while let Some(kind) = rest.chars().next() {
+            let (name, after) = ident(&rest[kind.len_utf8()..]);
+            if name.is_empty() {
+                return Err(invalid());
+            }
+            match kind {
+                '.' => compound.classes.push(name.to_string()),
+                '#' => compound.id = Some(name.to_string()),
+                _ => return Err(invalid()),
+            }
+            rest = after;
+        }
+
+        Ok(compound)
+    }
+
+    fn matches(&self, node: Node) -> bool {
+        let classes = node.attribute("class").unwrap_or_default();
+        self.tag
+            .as_ref()
+            .is_none_or(|t| node.has_tag_name(t.as_str()))
+            && self
+                .id
+                .as_deref()
+                .is_none_or(|id| node.attribute("id") == Some(id))
+            && self
+                .classes
+                .iter()
+                .all(|c| classes.split_whitespace().any(|n| n == c))
+    }
+}
+
+/// Split off the name at the start of `s`
+fn ident(s: &str) -> (&str, &str) {
+    let end = s
+        .find(|c: char| !(c.is_alphanumeric() || c == '-' || c == '_'))
+        .unwrap_or(s.len());
+    s.split_at(end)
+}
+
+/// Blank out `/* */` comments, keeping newlines so line numbers stay the same
+fn strip_comments(src: &str) -> String {
+    let mut res = String::with_capacity(src.len());
+    let mut rest = src;
+
+    while let Some(start) = rest.find("/*") {
+        res.push_str(&rest[..start]);
+        let end = rest[start..]
+            .find("*/")
+            .map_or(rest.len(), |i| start + i + 2);
+        res.extend(
+            rest[start..end]
+                .chars()
+                .map(|c| if c == '\n' { '\n' } else { ' ' }),
+        );
+        rest = &rest[end..];
+    }
+    res.push_str(rest);
+    res
+}
+
+/// Find the `}` closing a block, skipping quoted values and `{{placeholders}}`
+fn block_end(src: &str, from: usize) -> Option<usize> {
+    let mut quote: Option<char> = None;
+    let mut i = from;
+
+    while let Some(c) = src[i..].chars().next() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if src[i..].starts_with("{{") => {
+                i += src[i..].find("}}")? + 2;
+                continue;
+            }
+            None if c == '}' => return Some(i),
+            None if c == '{' => return None,
+            None => {}
+        }
+        i += c.len_utf8();
+    }
+    None
+}
+
+/// `name: value; name: value`, errors come with the offset they were found at
+fn parse_declarations(body: &str) -> Result<Vec<(String, String)>, (usize, String)> {
+    let mut res = vec![];
+
+    for (offset, decl) in split_declarations(body) {
+        if decl.trim().is_empty() {
+            continue;
+        }
+        let (name, value) = decl
+            .split_once(':')
+            .ok_or_else(|| (offset, format!("\"{}\" should be name: value", decl.trim())))?;
+        let name = name.trim();
+        if name.is_empty() || !ident(name).1.is_empty() {
+            return Err((offset, format!("invalid attribute name \"{name}\"")));
+        }
+        // elements silently skip what they don't take, so catch typos here
+        if !schema::is_known_attr(name) {
+            return Err((offset, format!("unknown attribute \"{name}\"")));
+        }
+        res.push((name.to_string(), unquote(value)));
+    }
+
+    Ok(res)
+}
+
+/// Split on `;` outside of quotes, with the offset of every part
+fn split_declarations(body: &str) -> Vec<(usize, &str)> {
+    let mut res = vec![];
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+
+    for (i, c) in body.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == ';' => {
+                res.push((start, &body[start..i]));
+                start = i + 1;
+            }
+            None => {}
+        }
+    }
+    res.push((start, &body[start..]));
+
+    // point at the first character of the declaration rather than the whitespace before it
+    res.into_iter()
+        .map(|(i, d)| (i + d.len() - d.trim_start().len(), d))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(src: &str) -> (usize, String) {
+        match Stylesheet::parse(src) {
+            Ok(_) => panic!("{src:?} should not parse"),
+            Err(e) => (e.line, e.message),
+        }
+    }
+
+    #[test]
+    fn selectors() {
+        let s = Stylesheet::parse("column.card#cpu .warn text, * { align: right }").unwrap();
+        assert_eq!(s.rules.len(), 2);
+
+        let Selector(compounds) = &s.rules[0].selector;
+        assert_eq!(compounds.len(), 3);
+        assert_eq!(compounds[0].tag.as_deref(), Some("column"));
+        assert_eq!(compounds[0].id.as_deref(), Some("cpu"));
+        assert_eq!(compounds[0].classes, vec!["card".to_string()]);
+        assert_eq!(compounds[1].classes, vec!["warn".to_string()]);
+        assert_eq!(s.rules[0].selector.specificity(), (1, 2, 2));
+        assert_eq!(s.rules[1].selector.specificity(), (0, 0, 0));
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            error("text {}\n\ncolumn..a { border: all }"),
+            (3, "invalid selector \"column..a\"".to_string())
+        );
+        assert_eq!(
+            error("column {\n  border: all;\n  bordr: all\n}"),
+            (3, "unknown attribute \"bordr\"".to_string())
+        );
+        assert_eq!(
+            error("column {\n  border all\n}"),
+            (2, "\"border all\" should be name: value".to_string())
+        );
+        assert_eq!(
+            error("column { border: all"),
+            (1, "{ is never closed".to_string())
+        );
+        assert_eq!(
+            error("\n, text { align: left }"),
+            (2, "empty selector".to_string())
+        );
+    }
+
+    #[test]
+    fn comments() {
+        let s = Stylesheet::parse("/* column { */ text { /* ; */ align: right }").unwrap();
+        assert_eq!(
+            s.rules[0].declarations,
+            vec![("align".to_string(), "right".to_string())]
+        );
+
+        // lines inside comments still count
+        assert_eq!(error("/*\n\n*/ text {").0, 3);
+    }
+
+    #[test]
+    fn cascade() {
+        let s = Stylesheet::parse(
+            "#a { title: id }
+             column.c { title: class; border: t }
+             column { title: tag; borderType: plain }
+             column.c { border: b }
+             text { wrap: trim }
+             * { fill: blue }",
+        )
+        .unwrap();
+        let doc = Document::parse(
+            r#"<window><column id="a" class="c" borderType="thick"><text /></column></window>"#,
+        )
+        .unwrap();
+        let column = doc.root_element().first_child().unwrap();
+        let text = column.first_child().unwrap();
+
+        let defaults = s.defaults(column);
+        // ids over classes over tags
+        assert_eq!(defaults["title"], "id");
+        // equally specific, the later rule wins
+        assert_eq!(defaults["border"], "b");
+        assert_eq!(defaults["borderType"], "plain");
+        // columns don't take fill
+        assert!(!defaults.contains_key("fill"));
+
+        // written on the element wins over the stylesheet
+        assert_eq!(s.attribute(column, "borderType").as_deref(), Some("thick"));
+        assert_eq!(s.attribute(column, "title").as_deref(), Some("id"));
+        assert_eq!(s.attribute(text, "wrap").as_deref(), Some("trim"));
+        assert_eq!(s.attribute(text, "title"), None);
+    }
+
+    #[test]
+    fn descendants() {
+        let s = Stylesheet::parse("window .card text { align: center }").unwrap();
+        let doc = Document::parse(
+            r#"<window><column class="card"><row><text /></row></column><text /></window>"#,
+        )
+        .unwrap();
+        let root = doc.root_element();
+        let nested = root.descendants().find(|n| n.has_tag_name("text")).unwrap();
+        let top = root.last_child().unwrap();
+
+        assert_eq!(s.attribute(nested, "align").as_deref(), Some("center"));
+        assert_eq!(s.attribute(top, "align"), None);
+    }
+}
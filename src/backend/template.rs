@@ -0,0 +1,416 @@
+use std::collections::BTreeMap;
+
+use color_eyre::eyre::{Error, Result};
+
+use crate::backend::{
+    filters,
+    value::{Value, lookup},
+};
+
+/// Attribute value split into literal text and `{{key}}` placeholders
+///
+/// - `\{{` is an escaped brace and is kept as a literal `{{`
+/// - `{{` without a closing `}}`, stray `}}` and empty placeholders are kept as literal text
+/// - `{{key | filter | filter(arg, "arg")}}` pipes the value through filters, left to right
+/// - `{{key ?? "default"}}` is used when the key is missing, filters are not applied to it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Part {
+    Literal(String),
+    Placeholder(Placeholder),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Placeholder {
+    key: String,
+    default: Option<String>,
+    filters: Vec<Filter>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Filter {
+    name: String,
+    args: Vec<String>,
+}
+
+/// What to show for placeholders whose key is missing from the store and which have no default
+#[derive(Clone, Debug, Default, PartialEq)]
+pub enum MissingKeys {
+    /// Nothing at all
+    #[default]
+    Blank,
+    /// Fixed text
+    Placeholder(String),
+    /// A visible marker naming the key
+    Error,
+}
+
+impl MissingKeys {
+    /// Policy from the `missing` and `missingText` attributes of `<window>`
+    pub fn from_attrs(missing: Option<&str>, text: Option<&str>) -> Result<Self> {
+        match missing {
+            None | Some("blank") => Ok(Self::Blank),
+            Some("placeholder") => Ok(Self::Placeholder(text.unwrap_or("-").to_string())),
+            Some("error") => Ok(Self::Error),
+            Some(m) => Err(Error::msg(format!(
+                "Invalid missing key policy \"{m}\" (should be blank, placeholder or error)"
+            ))),
+        }
+    }
+
+    fn fill(&self, key: &str) -> String {
+        match self {
+            Self::Blank => String::new(),
+            Self::Placeholder(p) => p.clone(),
+            Self::Error => format!("⚠ {key}"),
+        }
+    }
+}
+
+impl Placeholder {
+    fn parse(s: &str) -> Self {
+        let mut pipes = split_unquoted(s, "|").into_iter();
+        let head = pipes.next().unwrap_or_default();
+        let filters = pipes.map(Filter::parse).collect();
+
+        let (key, default) = match head.split_once("??") {
+            Some((key, default)) => (key, Some(unquote(default))),
+            None => (head, None),
+        };
+
+        Self {
+            key: key.trim().to_string(),
+            default,
+            filters,
+        }
+    }
+
+    fn render(&self, store: &BTreeMap<String, Value>, missing: &MissingKeys) -> Result<String> {
+        let Some(val) = lookup(store, &self.key) else {
+            return Ok(match &self.default {
+                Some(d) => d.clone(),
+                None => missing.fill(&self.key),
+            });
+        };
+
+        self.filters
+            .iter()
+            .try_fold(val.to_string(), |v, f| filters::apply(&f.name, &v, &f.args))
+    }
+}
+
+impl Filter {
+    /// `name` or `name(arg, arg, ...)`
+    fn parse(s: &str) -> Self {
+        let s = s.trim();
+        let Some((name, args)) = s.strip_suffix(')').and_then(|s| s.split_once('(')) else {
+            return Self {
+                name: s.to_string(),
+                args: vec![],
+            };
+        };
+
+        let args = if args.trim().is_empty() {
+            vec![]
+        } else {
+            split_unquoted(args, ",").into_iter().map(unquote).collect()
+        };
+
+        Self {
+            name: name.trim().to_string(),
+            args,
+        }
+    }
+}
+
+/// Split on `sep`, except inside quotes
+fn split_unquoted<'a>(s: &'a str, sep: &str) -> Vec<&'a str> {
+    let mut res = vec![];
+    let mut quote: Option<char> = None;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if s[i..].starts_with(sep) && i >= start => {
+                res.push(&s[start..i]);
+                start = i + sep.len();
+            }
+            None => {}
+        }
+    }
+    res.push(&s[start..]);
+
+    res
+}
+
+/// Split on `sep`, leaving placeholders whole
+pub fn split_template(s: &str, sep: char) -> Vec<&str> {
+    let mut res = vec![];
+    let mut in_placeholder = false;
+    let mut start = 0;
+
+    for (i, c) in s.char_indices() {
+        if s[i..].starts_with("{{") {
+            in_placeholder = true;
+        } else if in_placeholder && s[i..].starts_with("}}") {
+            in_placeholder = false;
+        } else if !in_placeholder && c == sep {
+            res.push(&s[start..i]);
+            start = i + c.len_utf8();
+        }
+    }
+    res.push(&s[start..]);
+
+    res
+}
+
+/// Trim and strip surrounding quotes
+pub fn unquote(s: &str) -> String {
+    let s = s.trim();
+    for q in ['"', '\''] {
+        if let Some(inner) = s.strip_prefix(q).and_then(|s| s.strip_suffix(q)) {
+            return inner.to_string();
+        }
+    }
+    s.to_string()
+}
+
+impl Template {
+    pub fn parse(s: &str) -> Self {
+        let mut parts = vec![];
+        let mut literal = String::new();
+        let mut rest = s;
+
+        while let Some(start) = rest.find("{{") {
+            let (before, after) = rest.split_at(start);
+
+            // escaped
+            if let Some(before) = before.strip_suffix('\\') {
+                literal.push_str(before);
+                literal.push_str("{{");
+                rest = &after[2..];
+                continue;
+            }
+            literal.push_str(before);
+
+            let Some(end) = after[2..].find("}}") else {
+                // never closed, the rest is plain text
+                literal.push_str(after);
+                rest = "";
+                break;
+            };
+
+            // "{{ a {{b}}" only templates the innermost opening
+            let mut inner = &after[2..end + 2];
+            if let Some(i) = inner.rfind("{{") {
+                literal.push_str("{{");
+                literal.push_str(&inner[..i]);
+                inner = &inner[i + 2..];
+            }
+
+            let key = inner.trim();
+            if key.is_empty() {
+                literal.push_str("{{");
+                literal.push_str(inner);
+                literal.push_str("}}");
+            } else {
+                if !literal.is_empty() {
+                    parts.push(Part::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(Part::Placeholder(Placeholder::parse(key)));
+            }
+
+            rest = &after[end + 4..];
+        }
+
+        literal.push_str(rest);
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+
+        Self { parts }
+    }
+
+    pub fn has_placeholders(&self) -> bool {
+        self.parts.iter().any(|p| matches!(p, Part::Placeholder(_)))
+    }
+
+    /// Fill every placeholder from `store`
+    pub fn render(&self, store: &BTreeMap<String, Value>, missing: &MissingKeys) -> Result<String> {
+        let mut res = String::new();
+
+        for part in &self.parts {
+            match part {
+                Part::Literal(l) => res.push_str(l),
+                Part::Placeholder(p) => res.push_str(&p.render(store, missing)?),
+            }
+        }
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store(values: &[(&str, &str)]) -> BTreeMap<String, Value> {
+        values
+            .iter()
+            .map(|(k, v)| (k.to_string(), Value::from(*v)))
+            .collect()
+    }
+
+    fn render(template: &str, values: &[(&str, &str)]) -> String {
+        Template::parse(template)
+            .render(&store(values), &MissingKeys::Blank)
+            .unwrap()
+    }
+
+    fn placeholder(key: &str) -> Part {
+        Part::Placeholder(Placeholder {
+            key: key.to_string(),
+            default: None,
+            filters: vec![],
+        })
+    }
+
+    #[test]
+    fn several_placeholders() {
+        let t = Template::parse("{{cpu}}% / {{mem}}%");
+        assert_eq!(
+            t.parts,
+            vec![
+                placeholder("cpu"),
+                Part::Literal("% / ".to_string()),
+                placeholder("mem"),
+                Part::Literal("%".to_string()),
+            ]
+        );
+        assert_eq!(
+            render("{{cpu}}% / {{mem}}%", &[("cpu", "12"), ("mem", "40")]),
+            "12% / 40%"
+        );
+    }
+
+    #[test]
+    fn repeated_key() {
+        assert_eq!(render("{{a}}-{{ a }}", &[("a", "x")]), "x-x");
+    }
+
+    #[test]
+    fn escaped_braces() {
+        let t = Template::parse(r"\{{cpu}} {{cpu}}");
+        assert_eq!(
+            t.parts,
+            vec![Part::Literal("{{cpu}} ".to_string()), placeholder("cpu")]
+        );
+    }
+
+    #[test]
+    fn nested_opening() {
+        let t = Template::parse("{{ a {{b}}");
+        assert_eq!(
+            t.parts,
+            vec![Part::Literal("{{ a ".to_string()), placeholder("b")]
+        );
+    }
+
+    #[test]
+    fn empty_placeholder() {
+        let t = Template::parse("a {{}} {{ }}");
+        assert!(!t.has_placeholders());
+        assert_eq!(render("a {{}} {{ }}", &[]), "a {{}} {{ }}");
+    }
+
+    #[test]
+    fn unclosed() {
+        let t = Template::parse("{{a}} {{b");
+        assert_eq!(
+            t.parts,
+            vec![placeholder("a"), Part::Literal(" {{b".to_string())]
+        );
+        assert_eq!(render("x }} {{b", &[("b", "1")]), "x }} {{b");
+    }
+
+    #[test]
+    fn filter_arguments() {
+        let t = Template::parse(r#"{{x | truncate(3, "…")}}"#);
+        assert_eq!(
+            t.parts,
+            vec![Part::Placeholder(Placeholder {
+                key: "x".to_string(),
+                default: None,
+                filters: vec![Filter {
+                    name: "truncate".to_string(),
+                    args: vec!["3".to_string(), "…".to_string()],
+                }],
+            })]
+        );
+        assert_eq!(
+            render(r#"{{x | truncate(3, "…")}}"#, &[("x", "abcdef")]),
+            "ab…"
+        );
+    }
+
+    #[test]
+    fn quoted_separators() {
+        let t = Template::parse(r#"{{x | pad(4) | truncate(2, "|,")}}"#);
+        let Part::Placeholder(p) = &t.parts[0] else {
+            panic!("not a placeholder");
+        };
+        assert_eq!(p.filters.len(), 2);
+        assert_eq!(p.filters[1].args, vec!["2".to_string(), "|,".to_string()]);
+    }
+
+    #[test]
+    fn defaults() {
+        assert_eq!(render("{{a ?? none}}", &[]), "none");
+        assert_eq!(render("{{a ?? none}}", &[("a", "1")]), "1");
+        assert_eq!(render(r#"{{a ?? " ?? "}}"#, &[]), " ?? ");
+        // filters only apply to values from the store
+        assert_eq!(render("{{a ?? x | upper}}", &[]), "x");
+        assert_eq!(render("{{a ?? x | upper}}", &[("a", "b")]), "B");
+    }
+
+    #[test]
+    fn missing_keys() {
+        let t = Template::parse("[{{a}}]");
+        let render = |missing| t.render(&store(&[]), &missing).unwrap();
+        assert_eq!(render(MissingKeys::Blank), "[]");
+        assert_eq!(render(MissingKeys::Placeholder("-".to_string())), "[-]");
+        assert_eq!(render(MissingKeys::Error), "[⚠ a]");
+
+        // a default wins over the policy
+        let t = Template::parse("{{a ?? 0}}");
+        assert_eq!(t.render(&store(&[]), &MissingKeys::Error).unwrap(), "0");
+    }
+
+    #[test]
+    fn missing_keys_from_attrs() {
+        assert_eq!(
+            MissingKeys::from_attrs(None, Some("?")).unwrap(),
+            MissingKeys::Blank
+        );
+        assert_eq!(
+            MissingKeys::from_attrs(Some("placeholder"), None).unwrap(),
+            MissingKeys::Placeholder("-".to_string())
+        );
+        assert_eq!(
+            MissingKeys::from_attrs(Some("placeholder"), Some("?")).unwrap(),
+            MissingKeys::Placeholder("?".to_string())
+        );
+        assert_eq!(
+            MissingKeys::from_attrs(Some("error"), None).unwrap(),
+            MissingKeys::Error
+        );
+        assert!(MissingKeys::from_attrs(Some("loud"), None).is_err());
+    }
+}
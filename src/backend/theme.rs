@@ -0,0 +1,181 @@
+//! Colours, as written in layouts
+//!
+//! Besides the names ratatui knows, colours can be `#rrggbb`, `rgb(r, g, b)`, a 256 colour index
+//! or `@name` from the palette set on `<window>`. RGB colours are turned into the closest of the
+//! 256 colours when the terminal can't show them.
+
+use std::{collections::BTreeMap, env, str::FromStr, sync::LazyLock};
+
+use parking_lot::{RwLock, const_rwlock};
+use ratatui::style::Color;
+
+static PALETTE: RwLock<BTreeMap<String, Color>> = const_rwlock(BTreeMap::new());
+
+/// Terminals announce 24 bit colour support through `COLORTERM`
+static TRUECOLOR: LazyLock<bool> =
+    LazyLock::new(|| env::var("COLORTERM").is_ok_and(|c| c == "truecolor" || c == "24bit"));
+
+/// A colour from a layout, already resolved and downgraded for the terminal
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ThemeColor(pub Color);
+
+impl From<ThemeColor> for Color {
+    fn from(c: ThemeColor) -> Self {
+        c.0
+    }
+}
+
+impl FromStr for ThemeColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let color = match s.strip_prefix('@') {
+            Some(name) => PALETTE
+                .read()
+                .get(name)
+                .copied()
+                .ok_or_else(|| format!("\"{name}\" is not in the palette"))?,
+            None => parse_color(s)?,
+        };
+
+        Ok(Self(downgrade(color)))
+    }
+}
+
+/// Parse a colour that is not a palette reference
+pub fn parse_color(s: &str) -> Result<Color, String> {
+    let s = s.trim();
+
+    if let Some(args) = s.strip_prefix("rgb(").and_then(|a| a.strip_suffix(')')) {
+        let channels: Vec<u8> = args
+            .split(',')
+            .map(|c| c.trim().parse::<u8>())
+            .collect::<Result<_, _>>()
+            .map_err(|_| "rgb() takes three numbers from 0 to 255".to_string())?;
+        let [r, g, b] = channels[..] else {
+            return Err("rgb() takes three numbers from 0 to 255".to_string());
+        };
+        return Ok(Color::Rgb(r, g, b));
+    }
+
+    // names, #rrggbb and indexes
+    Color::from_str(s).map_err(|_| "not a color".to_string())
+}
+
+/// `name=color;name=color`
+pub fn parse_palette(s: &str) -> Result<BTreeMap<String, Color>, String> {
+    s.split(';')
+        .map(|e| e.trim())
+        .filter(|e| !e.is_empty())
+        .map(|e| {
+            let (name, color) = e
+                .split_once('=')
+                .ok_or_else(|| format!("\"{e}\" should be name=color"))?;
+            let color = parse_color(color).map_err(|err| format!("\"{color}\": {err}"))?;
+            Ok((name.trim().to_string(), color))
+        })
+        .collect()
+}
+
+/// Replace the palette `@name` colours are looked up in, returns the previous one
+pub fn set_palette(palette: BTreeMap<String, Color>) -> BTreeMap<String, Color> {
+    std::mem::replace(&mut *PALETTE.write(), palette)
+}
+
+fn downgrade(color: Color) -> Color {
+    match color {
+        Color::Rgb(r, g, b) if !*TRUECOLOR => Color::Indexed(to_indexed(r, g, b)),
+        c => c,
+    }
+}
+
+/// Closest colour of the 6x6x6 cube or the grey ramp of the 256 colour palette
+fn to_indexed(r: u8, g: u8, b: u8) -> u8 {
+    const LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+    let level = |v: u8| {
+        (0..LEVELS.len())
+            .min_by_key(|&i| LEVELS[i].abs_diff(v))
+            .unwrap_or(0)
+    };
+    let distance = |(r2, g2, b2): (u8, u8, u8)| {
+        [(r, r2), (g, g2), (b, b2)]
+            .iter()
+            .map(|(a, b)| (a.abs_diff(*b) as u32).pow(2))
+            .sum::<u32>()
+    };
+
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (LEVELS[ri], LEVELS[gi], LEVELS[bi]);
+    let cube_index = 16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8;
+
+    // greys go from 8 to 238 in steps of 10
+    let avg = (r as u32 + g as u32 + b as u32) / 3;
+    let grey_step = (avg.saturating_sub(3) / 10).min(23) as u8;
+    let grey = 8 + grey_step * 10;
+    let grey_index = 232 + grey_step;
+
+    if distance((grey, grey, grey)) < distance(cube) {
+        grey_index
+    } else {
+        cube_index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indexed() {
+        assert_eq!(to_indexed(255, 0, 0), 196);
+        assert_eq!(to_indexed(0, 0, 0), 16);
+        assert_eq!(to_indexed(255, 255, 255), 231);
+        assert_eq!(to_indexed(0, 135, 215), 32);
+        // greys land on the ramp
+        assert_eq!(to_indexed(128, 128, 128), 244);
+        assert_eq!(to_indexed(8, 8, 8), 232);
+        assert_eq!(to_indexed(238, 238, 238), 255);
+        // close enough to the cube
+        assert_eq!(to_indexed(95, 95, 95), 59);
+    }
+
+    #[test]
+    fn palettes() {
+        let palette = parse_palette(" accent = #ff0000 ; dim=8;").unwrap();
+        assert_eq!(
+            palette,
+            BTreeMap::from([
+                ("accent".to_string(), Color::Rgb(255, 0, 0)),
+                ("dim".to_string(), Color::Indexed(8)),
+            ])
+        );
+
+        assert_eq!(
+            parse_palette("accent").unwrap_err(),
+            "\"accent\" should be name=color"
+        );
+        assert_eq!(
+            parse_palette("a=red;b=zzz").unwrap_err(),
+            "\"zzz\": not a color"
+        );
+        assert_eq!(
+            parse_palette("a=rgb(300, 0, 0)").unwrap_err(),
+            "\"rgb(300, 0, 0)\": rgb() takes three numbers from 0 to 255"
+        );
+    }
+
+    #[test]
+    fn palette_references() {
+        let previous = set_palette(parse_palette("accent=cyan").unwrap());
+        let accent = ThemeColor::from_str("@accent");
+        let unknown = ThemeColor::from_str("@nope");
+        set_palette(previous);
+
+        assert_eq!(accent.map(|c| c.0), Ok(Color::Cyan));
+        assert_eq!(
+            unknown.map(|c| c.0),
+            Err("\"nope\" is not in the palette".to_string())
+        );
+    }
+}
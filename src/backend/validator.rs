@@ -0,0 +1,250 @@
+use std::fmt;
+
+use roxmltree::{Document, Node, TextPos};
+
+use crate::backend::{
+    ComponentType,
+    schema::{AttrDef, ElementDef, ElementKind},
+    stylesheet::Stylesheet,
+    template::Template,
+};
+
+/// Stand-in definition for unknown elements
+const PLUGIN: ElementDef = ElementDef {
+    tag: "",
+    kind: ElementKind::Plugin,
+    attrs: &[],
+};
+
+/// A problem found in a layout, with the position it was found at
+pub struct Diagnostic {
+    pub pos: TextPos,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.pos.row, self.pos.col, self.message)
+    }
+}
+
+/// Walk the whole document and collect every problem instead of stopping at the first one
+///
+/// Attributes given by the stylesheet are checked like the ones written on the element.
+pub fn validate(doc: &Document, sheet: &Stylesheet) -> Vec<Diagnostic> {
+    let mut diagnostics = vec![];
+    let root = doc.root_element();
+
+    if root.tag_name().name() != "window" {
+        diagnostics.push(Diagnostic {
+            pos: doc.text_pos_at(root.range().start),
+            message: format!(
+                "Invalid root tag <{}> (should be <window>)",
+                root.tag_name().name()
+            ),
+        });
+    }
+
+    recurse(doc, root, sheet, &mut diagnostics);
+    diagnostics
+}
+
+fn recurse(doc: &Document, node: Node, sheet: &Stylesheet, diagnostics: &mut Vec<Diagnostic>) {
+    let tag = node.tag_name().name();
+    let ct = ComponentType::from_tag(tag);
+    let pos = doc.text_pos_at(node.range().start);
+
+    if ct == ComponentType::Window && node != doc.root_element() {
+        diagnostics.push(Diagnostic {
+            pos,
+            message: "<window> is only allowed as the root tag".to_string(),
+        });
+    }
+
+    check_attributes(doc, node, sheet, diagnostics);
+
+    let kind = ElementDef::find(tag).map(|d| d.kind);
+    let in_text = matches!(kind, Some(ElementKind::Text | ElementKind::Inline));
+
+    for child in node.children().filter(|c| c.is_element()) {
+        let child_tag = child.tag_name().name();
+        let child_kind = ElementDef::find(child_tag).map(|d| d.kind);
+        let child_inline = child_kind == Some(ElementKind::Inline);
+
+        if kind == Some(ElementKind::Style) {
+            diagnostics.push(Diagnostic {
+                pos: doc.text_pos_at(child.range().start),
+                message: format!("<{child_tag}> is not allowed inside <{tag}>"),
+            });
+        } else if child_kind == Some(ElementKind::Style) && node != doc.root_element() {
+            diagnostics.push(Diagnostic {
+                pos: doc.text_pos_at(child.range().start),
+                message: format!("<{child_tag}> is only allowed directly inside <window>"),
+            });
+        } else if in_text && !child_inline {
+            diagnostics.push(Diagnostic {
+                pos: doc.text_pos_at(child.range().start),
+                message: format!("<{child_tag}> is not allowed inside <{tag}>, only markup is"),
+            });
+        } else if !in_text && child_inline {
+            diagnostics.push(Diagnostic {
+                pos: doc.text_pos_at(child.range().start),
+                message: format!("<{child_tag}> is only allowed inside <text>"),
+            });
+        } else if !in_text && !ct.is_layout() {
+            diagnostics.push(Diagnostic {
+                pos: doc.text_pos_at(child.range().start),
+                message: format!(
+                    "<{}> can not have children, <{}> is not a layout",
+                    child.tag_name().name(),
+                    tag
+                ),
+            });
+        }
+        recurse(doc, child, sheet, diagnostics);
+    }
+}
+
+fn check_attributes(
+    doc: &Document,
+    node: Node,
+    sheet: &Stylesheet,
+    diagnostics: &mut Vec<Diagnostic>,
+) {
+    let tag = node.tag_name().name();
+    let pos = doc.text_pos_at(node.range().start);
+    let defaults = sheet.defaults(node);
+
+    // unknown elements are plugins, which may take any attribute
+    let (def, any_attrs) = match ElementDef::find(tag) {
+        Some(def) => (def, false),
+        None => (&PLUGIN, true),
+    };
+
+    for attr in node.attributes() {
+        let pos = doc.text_pos_at(attr.range().start);
+
+        let Some(a) = def.all_attrs().find(|a| a.name == attr.name()) else {
+            if !any_attrs {
+                diagnostics.push(Diagnostic {
+                    pos,
+                    message: format!("Unknown attribute \"{}\" on <{tag}>", attr.name()),
+                });
+            }
+            continue;
+        };
+
+        if let Some(e) = check_value(a, attr.value()) {
+            diagnostics.push(Diagnostic {
+                pos,
+                message: format!("Invalid {} \"{}\": {e}", attr.name(), attr.value()),
+            });
+        }
+    }
+
+    // attributes on the element itself are checked above
+    for (name, value) in defaults
+        .iter()
+        .filter(|(n, _)| node.attribute(n.as_str()).is_none())
+    {
+        let Some(a) = def.all_attrs().find(|a| a.name == name) else {
+            continue;
+        };
+
+        if let Some(e) = check_value(a, value) {
+            diagnostics.push(Diagnostic {
+                pos,
+                message: format!("Invalid {name} \"{value}\" from the stylesheet: {e}"),
+            });
+        }
+    }
+
+    for a in def.all_attrs().filter(|a| a.required) {
+        if node.attribute(a.name).is_none() && !defaults.contains_key(a.name) {
+            diagnostics.push(Diagnostic {
+                pos,
+                message: format!("<{tag}> is missing the required attribute \"{}\"", a.name),
+            });
+        }
+    }
+}
+
+/// What is wrong with `value`, if anything
+fn check_value(a: &AttrDef, value: &str) -> Option<String> {
+    // templated values are only known once the plugin has run
+    if Template::parse(value).has_placeholders() {
+        return (!a.templated)
+            .then(|| "can't be templated, it is read once when the layout is loaded".to_string());
+    }
+
+    a.ty.check(value).err()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(layout: &str) -> Vec<String> {
+        let doc = Document::parse(layout).unwrap();
+        validate(&doc, &Stylesheet::default())
+            .iter()
+            .map(|d| d.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn every_problem() {
+        let layout = r#"<window>
+    <column padding="1 2 3 4 5" colour="red">
+        <block><text>x</text></block>
+        <gauge />
+        <text>a <column /> <b>b</b></text>
+    </column>
+</window>"#;
+
+        assert_eq!(
+            check(layout),
+            vec![
+                r#"2:13: Invalid padding "1 2 3 4 5": should be 1 to 4 whole numbers"#,
+                r#"2:33: Unknown attribute "colour" on <column>"#,
+                "3:16: <text> can not have children, <block> is not a layout",
+                r#"4:9: <gauge> is missing the required attribute "value""#,
+                "5:17: <column> is not allowed inside <text>, only markup is",
+            ]
+        );
+    }
+
+    #[test]
+    fn valid() {
+        let layout = r#"<window missing="error">
+    <exec cmd="date" interval="5s">
+        <text size="1" align="{{stdout}}">{{stdout}} <b>!</b></text>
+    </exec>
+</window>"#;
+
+        assert!(check(layout).is_empty());
+    }
+
+    #[test]
+    fn from_the_stylesheet() {
+        let doc = Document::parse("<window>\n  <column><text /></column>\n</window>").unwrap();
+        let sheet = Stylesheet::parse("column { padding: x }").unwrap();
+        let diagnostics: Vec<String> = validate(&doc, &sheet)
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+
+        assert_eq!(
+            diagnostics,
+            vec![r#"2:3: Invalid padding "x" from the stylesheet: should be 1 to 4 whole numbers"#]
+        );
+    }
+
+    #[test]
+    fn root() {
+        assert_eq!(
+            check("<column />"),
+            vec!["1:1: Invalid root tag <column> (should be <window>)"]
+        );
+    }
+}
@@ -0,0 +1,104 @@
+//! Values kept in the store of a plugin
+
+use std::{collections::BTreeMap, fmt};
+
+/// Either plain text, or structured data such as a list of rows
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    Text(String),
+    List(Vec<Value>),
+    Map(BTreeMap<String, Value>),
+}
+
+impl Value {
+    /// Plain values that are a number
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            Value::Text(t) => t.trim().parse::<f64>().ok().filter(|n| n.is_finite()),
+            _ => None,
+        }
+    }
+
+    /// Walk into lists (by index) and maps (by key) along a dotted path, e.g. `0.name`
+    pub fn get_path(&self, path: &str) -> Option<&Value> {
+        path.split('.').try_fold(self, |v, segment| match v {
+            Value::List(l) => l.get(segment.parse::<usize>().ok()?),
+            Value::Map(m) => m.get(segment),
+            Value::Text(_) => None,
+        })
+    }
+
+    /// Rows of a list, or the values of a map
+    pub fn items(&self) -> Vec<&Value> {
+        match self {
+            Value::Text(_) => vec![self],
+            Value::List(l) => l.iter().collect(),
+            Value::Map(m) => m.values().collect(),
+        }
+    }
+}
+
+/// Look up `key` in a store
+///
+/// Keys may point into structured values, `procs.0.name` is the name of the first row of
+/// `procs`. Keys that contain dots themselves (like `cpu.0`) are found as well.
+pub fn lookup<'a>(store: &'a BTreeMap<String, Value>, key: &str) -> Option<&'a Value> {
+    if let Some(v) = store.get(key) {
+        return Some(v);
+    }
+
+    // longest stored prefix first
+    key.rmatch_indices('.')
+        .find_map(|(i, _)| store.get(&key[..i])?.get_path(&key[i + 1..]))
+}
+
+/// Lists and maps are joined with `, `, so `{{lines}}` shows something sensible
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Text(t) => f.write_str(t),
+            Value::List(l) => {
+                for (i, v) in l.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{v}")?;
+                }
+                Ok(())
+            }
+            Value::Map(m) => {
+                for (i, (k, v)) in m.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(", ")?;
+                    }
+                    write!(f, "{k}: {v}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl From<String> for Value {
+    fn from(s: String) -> Self {
+        Value::Text(s)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Text(s.to_string())
+    }
+}
+
+impl From<Vec<Value>> for Value {
+    fn from(l: Vec<Value>) -> Self {
+        Value::List(l)
+    }
+}
+
+impl From<BTreeMap<String, Value>> for Value {
+    fn from(m: BTreeMap<String, Value>) -> Self {
+        Value::Map(m)
+    }
+}
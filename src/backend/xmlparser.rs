@@ -3,53 +3,122 @@ use color_eyre::{
     eyre::{Context, Error},
 };
 use parking_lot::lock_api::RwLock;
-use ratatui::layout::Constraint;
+use ratatui::{layout::Constraint, style::Color};
 use roxmltree::{Document, Node, ParsingOptions};
 
-use std::{cell::RefCell, collections::BTreeMap, fs, path::PathBuf, rc::Rc, sync::Arc};
+use std::{
+    cell::RefCell,
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::Arc,
+    time::Duration,
+};
+
+use crate::{
+    backend::{
+        Attribute, ComponentType, History, RTRef, RenderTree, Store, SubRoutine,
+        markup::{self, Segment, SegmentStyle},
+        modules::{create_renderer, get_subroutine},
+        stylesheet::{self, Stylesheet},
+        template::MissingKeys,
+        theme,
+        validator::{self, Diagnostic},
+    },
+    utils::{bool_from_optstr, duration_from_str},
+};
 
-use crate::{backend::{
-    modules::create_renderer, Attribute, ComponentType, RTRef, RenderTree, Store, SubRoutine
-}, utils::read_opt_attributes};
+/// Interval used for subroutines without an `interval` attribute
+const DEFAULT_INTERVAL: Duration = Duration::from_secs(1);
 
 pub struct Parser {
     components: Vec<RTRef>,
     subroutines: Vec<SubRoutine>,
     contents: String,
+    /// External stylesheets are relative to the layout
+    dir: PathBuf,
+    stylesheet: Stylesheet,
+    missing: MissingKeys,
 }
 
 impl Parser {
     pub fn new<P: Into<PathBuf>>(p: P) -> Result<Self> {
+        let path = p.into();
         Ok(Self {
             components: vec![],
             subroutines: vec![],
-            contents: fs::read_to_string(p.into())?,
+            contents: fs::read_to_string(&path)?,
+            dir: path.parent().unwrap_or(Path::new(".")).to_path_buf(),
+            stylesheet: Stylesheet::default(),
+            missing: MissingKeys::default(),
         })
     }
 
-    pub fn parse(mut self) -> Result<Self> {
-        let opts = ParsingOptions {
+    fn options() -> ParsingOptions {
+        ParsingOptions {
             allow_dtd: true,
             ..ParsingOptions::default()
-        };
+        }
+    }
+
+    pub fn parse(mut self) -> Result<Self> {
         let contents_clone = self.contents.clone();
-        let doc = Document::parse_with_options(&contents_clone, opts)?;
+        let doc = Document::parse_with_options(&contents_clone, Self::options())?;
 
-        if doc.root_element().tag_name().name() != "window" {
-            panic!("Invalid root tag! (should be <window>)");
-        }
+        let (sheet, diagnostics) = Stylesheet::load(&doc, &self.dir);
+        self.stylesheet = sheet;
 
-        self.recurse(doc.root_element(), &doc, None)?;
+        // colours are checked against the new palette, the old one stays if the layout is refused
+        let previous = Self::load_palette(&doc, &self.stylesheet);
+        if let Err(e) = self.build(&doc, diagnostics) {
+            theme::set_palette(previous);
+            return Err(e);
+        }
 
         Ok(self)
     }
 
+    /// Validate the layout and build the tree, once the stylesheet and palette are loaded
+    fn build(&mut self, doc: &Document, mut diagnostics: Vec<Diagnostic>) -> Result<()> {
+        // refuse anything the schema doesn't allow, listing every problem at once
+        diagnostics.extend(validator::validate(doc, &self.stylesheet));
+        if !diagnostics.is_empty() {
+            let list = diagnostics
+                .iter()
+                .map(|d| d.to_string())
+                .collect::<Vec<String>>()
+                .join("\n");
+            return Err(Error::msg(format!("Invalid layout:\n{list}")));
+        }
+
+        // global settings live on the root
+        let root = doc.root_element();
+        self.missing = MissingKeys::from_attrs(
+            self.stylesheet.attribute(root, "missing").as_deref(),
+            self.stylesheet.attribute(root, "missingText").as_deref(),
+        )?;
+
+        self.recurse(root, doc, None)
+    }
+
     fn recurse(&mut self, node: Node, doc: &Document, parent: Option<RTRef>) -> Result<()> {
         if node.tag_name().name() == "window" && doc.root_element() != node {
             return Err(Error::msg("Window is only allowed as a root tag."));
         }
 
-        let (render_tree, subroutine, ct) = create_item(node)?;
+        // stylesheets were read before anything was built
+        if node.has_tag_name(stylesheet::TAG) {
+            return Ok(());
+        }
+
+        // children template from the store of the closest plugin above them
+        let inherited = parent.as_ref().and_then(|p| {
+            let p = p.borrow();
+            p.store.clone().zip(p.history.clone())
+        });
+        let (render_tree, subroutine, ct) =
+            create_item(node, inherited, &self.missing, &self.stylesheet)?;
 
         if let Some(s) = subroutine {
             self.subroutines.push(s);
@@ -86,86 +155,175 @@ impl Parser {
         Ok(())
     }
 
-    pub fn ret(self) -> Result<(Vec<Rc<RefCell<RenderTree>>>, Vec<SubRoutine>)> {
-        Ok((self.components, self.subroutines))
+    /// Collect every problem in the document without building anything
+    pub fn validate(&self) -> Result<Vec<Diagnostic>> {
+        let doc = Document::parse_with_options(&self.contents, Self::options())?;
+        let (sheet, mut diagnostics) = Stylesheet::load(&doc, &self.dir);
+        Self::load_palette(&doc, &sheet);
+        diagnostics.extend(validator::validate(&doc, &sheet));
+        Ok(diagnostics)
+    }
+
+    /// Use the palette of `<window>`, returns the previous palette
+    ///
+    /// An invalid palette is reported by validation, until then it is left empty
+    fn load_palette(doc: &Document, sheet: &Stylesheet) -> BTreeMap<String, Color> {
+        let palette = sheet
+            .attribute(doc.root_element(), "palette")
+            .and_then(|p| theme::parse_palette(&p).ok())
+            .unwrap_or_default();
+        theme::set_palette(palette)
     }
-}
 
+    /// Components, subroutines and the external stylesheets the layout uses
+    pub fn ret(self) -> Result<(Vec<RTRef>, Vec<SubRoutine>, Vec<PathBuf>)> {
+        Ok((self.components, self.subroutines, self.stylesheet.files))
+    }
+}
 
 /// Parse size from attribute
-/// 
+///
 /// Sizes:
 /// - X% == Percentage(X)
 /// - X/Y == Ratios(X,Y)
 /// - X/ == Fill(X)
 /// - X == Length(X)
 /// - _ == Fill(1)
-fn size_from_attr(ct: &ComponentType, attr: Option<String>) -> Result<Constraint> {
-    let val: Constraint;
-
-    // custom sizes are only allowed for layouts, ignored otherwise
-    if ct.is_layout()
-        && let Some(sz) = attr
-    {
-        let ratios = sz.split_terminator("/").collect::<Vec<&str>>();
-        // ratio (eg. 1/1)
-        if ratios.len() == 2 {
-            val = Constraint::Ratio(ratios[0].parse()?, ratios[1].parse()?);
-        } else {
-            // other type
-            val = match sz.char_indices().nth_back(0) {
-                Some((_, '%')) => Constraint::Percentage(sz.strip_suffix("%").unwrap().parse()?),
-                Some((_, '/')) => Constraint::Fill(sz.strip_suffix("/").unwrap().parse()?),
-                _ => Constraint::Length(sz.parse()?),
-            };
-        }
-    } else {
-        val = Constraint::Fill(1)
+/// - min:X == Min(X)
+/// - max:X == Max(X)
+fn size_from_attr(attr: Option<String>) -> Result<Constraint> {
+    match attr {
+        Some(sz) => size_from_str(&sz),
+        None => Ok(Constraint::Fill(1)),
     }
+}
+
+/// Parse a size, see `size_from_attr`
+pub fn size_from_str(sz: &str) -> Result<Constraint> {
+    if let Some(min) = sz.strip_prefix("min:") {
+        return Ok(Constraint::Min(min.trim().parse()?));
+    }
+    if let Some(max) = sz.strip_prefix("max:") {
+        return Ok(Constraint::Max(max.trim().parse()?));
+    }
+
+    let ratios = sz.split_terminator("/").collect::<Vec<&str>>();
+    // ratio (eg. 1/1)
+    let val = if ratios.len() == 2 {
+        Constraint::Ratio(ratios[0].parse()?, ratios[1].parse()?)
+    } else {
+        // other type
+        match sz.char_indices().nth_back(0) {
+            _ if sz == "_" => Constraint::Fill(1),
+            Some((_, '%')) => Constraint::Percentage(sz.strip_suffix("%").unwrap().parse()?),
+            Some((_, '/')) => Constraint::Fill(sz.strip_suffix("/").unwrap().parse()?),
+            _ => Constraint::Length(sz.parse()?),
+        }
+    };
     Ok(val)
 }
 
-fn create_item(node: Node) -> Result<(RTRef, Option<SubRoutine>, ComponentType)> {
+fn create_item(
+    node: Node,
+    inherited: Option<(Store, History)>,
+    missing: &MissingKeys,
+    sheet: &Stylesheet,
+) -> Result<(RTRef, Option<SubRoutine>, ComponentType)> {
     let t = node.tag_name().name();
     let ct = ComponentType::from_tag(t);
 
-    // create subroutine if needed
-    let sr: Option<SubRoutine>;
-    let store: Option<Store>;
-
-    if ct == ComponentType::Plugin {
-        // create clean data store if subroutine
-        let store_arc: Store = Arc::new(RwLock::new(BTreeMap::new()));
-        store = Some(store_arc.clone());
-        sr = None;
+    // create clean data store if subroutine
+    let (store, history): (Option<Store>, Option<History>) = if ct.is_plugin() {
+        (
+            Some(Arc::new(RwLock::new(BTreeMap::new()))),
+            Some(Arc::new(RwLock::new(BTreeMap::new()))),
+        )
     } else {
-        sr = None;
-        store = None;
-    }
+        inherited.unzip()
+    };
 
     /* Setup */
-    let pre_attributes = collect_attributes(&ct, node, &store);
+    let pre_attributes = collect_attributes(node, &store, missing, sheet);
 
     /* Properties */
 
-    let size_constraint = size_from_attr(&ct, read_opt_attributes(pre_attributes.get("size"))).wrap_err_with(|| {
+    // these are read once, before any plugin has run, so values still waiting on one count as unset
+    let fixed = |name: &str| {
+        pre_attributes
+            .get(name)
+            .and_then(|a| a.read().ok())
+            .filter(|v| !v.trim().is_empty())
+    };
+
+    let size = fixed("size");
+    let size_constraint = size_from_attr(size.clone()).wrap_err_with(|| {
         format!(
             "Failed to parse attribute size \"{}\"",
-            read_opt_attributes(pre_attributes.get("size")).unwrap(), // it is safe to unwrap here as it can only error if it is Some
+            size.unwrap_or_default()
         )
     })?;
 
     // set up renderer
     let attributes = Arc::new(RwLock::new(pre_attributes.clone()));
-    let renderer = create_renderer(&ct, store.clone(), attributes.clone());
+    let segments = if ct == ComponentType::Text {
+        let mut segments = vec![];
+        collect_segments(
+            node,
+            &SegmentStyle::default(),
+            &store,
+            missing,
+            sheet,
+            &mut segments,
+        );
+        segments
+    } else {
+        vec![]
+    };
+    let renderer = create_renderer(
+        &ct,
+        t,
+        store.clone(),
+        history.clone(),
+        attributes.clone(),
+        segments,
+    );
+
+    // create subroutine if needed
+    let sr = match (&store, &history) {
+        (Some(s), Some(h)) if ct.is_plugin() => {
+            let interval = match fixed("interval") {
+                Some(i) => duration_from_str(&i)
+                    .wrap_err_with(|| format!("Failed to parse attribute interval \"{i}\""))?,
+                None => DEFAULT_INTERVAL,
+            };
+
+            // clocks should tick with the wall clock unless told otherwise
+            let align = match fixed("alignTicks") {
+                Some(a) => bool_from_optstr(Some(a)),
+                None => ct == ComponentType::Clock,
+            };
+
+            Some(SubRoutine::new(
+                s.clone(),
+                h.clone(),
+                attributes.clone(),
+                interval,
+                align,
+                get_subroutine(&ct),
+            ))
+        }
+        _ => None,
+    };
 
     /* Final Object Creation */
     let rt = RenderTree {
         children: vec![],
-        store: store,
-        attributes: attributes,
+        store,
+        history,
+        attributes,
         size_constraint,
         ctype: ct,
+        tag: t.to_string(),
         renderer,
     };
 
@@ -173,39 +331,53 @@ fn create_item(node: Node) -> Result<(RTRef, Option<SubRoutine>, ComponentType)>
 }
 
 fn collect_attributes(
-    ct: &ComponentType,
     node: Node<'_, '_>,
     store: &Option<Store>,
+    missing: &MissingKeys,
+    sheet: &Stylesheet,
 ) -> BTreeMap<String, Attribute> {
     let mut pre_attributes: BTreeMap<String, Attribute> = BTreeMap::new();
+    // the stylesheet goes first, attributes on the element override it
+    for (name, value) in sheet.defaults(node) {
+        pre_attributes.insert(name, Attribute::create(value, store.clone(), missing));
+    }
+
     // map attributes and process core-attributes
     for x in node.attributes() {
         pre_attributes.insert(
             x.name().to_string(),
-            Attribute::create(x.value().to_string(), store.clone()),
+            Attribute::create(x.value().to_string(), store.clone(), missing),
         );
     }
 
-    // collect text for text module
-    if *ct == ComponentType::Text {
-        collect_text(node, &mut pre_attributes, store);
-    }
-
     pre_attributes
 }
 
-fn collect_text(node: Node, attributes: &mut BTreeMap<String, Attribute>, store: &Option<Store>) {
-    // collect all strings (and ignore all non-text children)
-    let res = node
-        .children()
-        .map(|f| {
-            if f.is_text() {
-                f.text().unwrap_or("")
-            } else {
-                ""
+/// Split the contents of `<text>` into runs of text, each styled by the markup around it
+fn collect_segments(
+    node: Node,
+    style: &SegmentStyle,
+    store: &Option<Store>,
+    missing: &MissingKeys,
+    sheet: &Stylesheet,
+    segments: &mut Vec<Segment>,
+) {
+    for child in node.children() {
+        if let Some(text) = child.text().filter(|_| child.is_text()) {
+            segments.push(Segment {
+                text: Attribute::create(text.to_string(), store.clone(), missing),
+                style: style.clone(),
+            });
+        } else if child.is_element() {
+            let mut inner = style.clone();
+            inner.modifiers |= markup::modifier(child.tag_name().name());
+            if let Some(fg) = sheet.attribute(child, "fg") {
+                inner.fg = Some(Attribute::create(fg, store.clone(), missing));
             }
-        })
-        .collect::<Vec<&str>>()
-        .join("");
-    attributes.insert("text".to_string(), Attribute::create(res, store.clone()));
+            if let Some(bg) = sheet.attribute(child, "bg") {
+                inner.bg = Some(Attribute::create(bg, store.clone(), missing));
+            }
+            collect_segments(child, &inner, store, missing, sheet, segments);
+        }
+    }
 }
@@ -0,0 +1,98 @@
+use std::{env, path::PathBuf};
+
+use color_eyre::eyre::{Error, Result};
+
+const HELP: &str = "\
+Usage: rpanel [OPTIONS] [LAYOUT]
+
+Arguments:
+  [LAYOUT]  Path to the layout file. Defaults to $XDG_CONFIG_HOME/rpanel/panel.xml,
+            then ~/.config/rpanel/panel.xml
+
+Options:
+  -c, --check    Validate the layout, print every problem and exit
+      --xsd      Print the XML schema for layouts
+  -h, --help     Print help
+  -V, --version  Print version";
+
+pub enum Action {
+    Run(PathBuf),
+    Check(PathBuf),
+    Xsd,
+    Help,
+    Version,
+}
+
+impl Action {
+    pub fn help() -> &'static str {
+        HELP
+    }
+
+    pub fn version() -> String {
+        format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    }
+
+    pub fn from_args() -> Result<Self> {
+        Self::parse(env::args().skip(1))
+    }
+
+    fn parse(args: impl Iterator<Item = String>) -> Result<Self> {
+        let mut layout: Option<PathBuf> = None;
+        let mut check = false;
+
+        for arg in args {
+            match arg.as_str() {
+                "-h" | "--help" => return Ok(Self::Help),
+                "-V" | "--version" => return Ok(Self::Version),
+                "-c" | "--check" => check = true,
+                "--xsd" => return Ok(Self::Xsd),
+                a if a.starts_with('-') && a != "-" => {
+                    return Err(Error::msg(format!(
+                        "Unknown option \"{a}\", see rpanel --help"
+                    )));
+                }
+                _ if layout.is_some() => {
+                    return Err(Error::msg(
+                        "Only one layout can be given, see rpanel --help",
+                    ));
+                }
+                _ => layout = Some(arg.into()),
+            }
+        }
+
+        let layout = match layout {
+            Some(l) => l,
+            None => discover()?,
+        };
+
+        if check {
+            Ok(Self::Check(layout))
+        } else {
+            Ok(Self::Run(layout))
+        }
+    }
+}
+
+/// Find the default layout, following the XDG base directory spec
+fn discover() -> Result<PathBuf> {
+    let mut candidates: Vec<PathBuf> = vec![];
+
+    if let Some(xdg) = env::var_os("XDG_CONFIG_HOME").filter(|x| !x.is_empty()) {
+        candidates.push(PathBuf::from(xdg).join("rpanel/panel.xml"));
+    }
+    if let Some(home) = env::var_os("HOME").filter(|h| !h.is_empty()) {
+        candidates.push(PathBuf::from(home).join(".config/rpanel/panel.xml"));
+    }
+
+    if let Some(found) = candidates.iter().find(|c| c.is_file()) {
+        return Ok(found.clone());
+    }
+
+    let tried = candidates
+        .iter()
+        .map(|c| format!("\n  {}", c.display()))
+        .collect::<String>();
+    Err(Error::msg(format!(
+        "No layout given and none found, tried:{tried}"
+    )))
+}
@@ -1,34 +1,146 @@
-use backend::{RTRef, RenderTree, SubRoutine, xmlparser};
-use color_eyre::eyre::Result;
+use backend::{RTRef, SubRoutine, scheduler::Scheduler, schema, xmlparser};
+use color_eyre::{
+    Report,
+    eyre::{Context, Result},
+};
 use crossterm::event::{self, Event};
-use std::{cell::RefCell, rc::Rc};
+use std::{
+    path::{Path, PathBuf},
+    time::Duration,
+};
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
 
-use crate::renderer::Renderer;
+use crate::{cli::Action, renderer::Renderer, watcher::Watcher};
 
 mod backend;
+mod cli;
 mod renderer;
 mod utils;
+mod watcher;
+
+/// How long the UI waits for input before checking for store updates
+const POLL_RATE: Duration = Duration::from_millis(50);
 
 #[tokio::main]
 async fn main() -> Result<()> {
     color_eyre::install()?;
-    let (render_tree, subroutines) = xmlparser::Parser::new("demo.xml")?.parse()?.ret()?;
-    dbg!(&render_tree);
+
+    let layout = match Action::from_args()? {
+        Action::Run(layout) => layout,
+        Action::Check(layout) => {
+            if !check(&layout)? {
+                std::process::exit(1);
+            }
+            return Ok(());
+        }
+        Action::Xsd => {
+            print!("{}", schema::xsd());
+            return Ok(());
+        }
+        Action::Help => {
+            println!("{}", Action::help());
+            return Ok(());
+        }
+        Action::Version => {
+            println!("{}", Action::version());
+            return Ok(());
+        }
+    };
+
+    let (render_tree, subroutines, stylesheets) = load(&layout)?;
+
+    // subroutines run on the runtime and notify the UI when their store changes
+    let (tx, rx) = mpsc::unbounded_channel();
+    let scheduler = Scheduler::start(subroutines, tx.clone());
 
     // UI can be synchronous, making it async makes no sense whatsoever
-    run(render_tree)?;
+    let res = run(layout, stylesheets, render_tree, scheduler, tx, rx);
     ratatui::restore();
-    Ok(())
+    res
+}
+
+/// Print every problem in the layout, returns false if there were any
+fn check(layout: &Path) -> Result<bool> {
+    let parser = xmlparser::Parser::new(layout)
+        .wrap_err_with(|| format!("Failed to read layout {}", layout.display()))?;
+
+    // not even valid XML
+    let diagnostics = match parser.validate() {
+        Ok(d) => d,
+        Err(e) => {
+            eprintln!("{}: {e}", layout.display());
+            return Ok(false);
+        }
+    };
+
+    for d in &diagnostics {
+        eprintln!("{}:{d}", layout.display());
+    }
+
+    Ok(diagnostics.is_empty())
+}
+
+/// Components, subroutines and the external stylesheets to watch
+fn load(layout: &Path) -> Result<(Vec<RTRef>, Vec<SubRoutine>, Vec<PathBuf>)> {
+    xmlparser::Parser::new(layout)
+        .wrap_err_with(|| format!("Failed to read layout {}", layout.display()))?
+        .parse()?
+        .ret()
 }
 
-fn run(rt: Vec<RTRef>) -> color_eyre::Result<()> {
+fn run(
+    layout: PathBuf,
+    stylesheets: Vec<PathBuf>,
+    rt: Vec<RTRef>,
+    mut scheduler: Scheduler,
+    notify: UnboundedSender<()>,
+    mut updates: UnboundedReceiver<()>,
+) -> color_eyre::Result<()> {
     let mut terminal = ratatui::init();
-    let renderer = Renderer::new(rt);
+    let mut renderer = Renderer::new(rt);
+    let mut watcher = Watcher::new(&layout);
+    watcher.also_watch(stylesheets);
+    // error from the last reload, shown on top of the old tree
+    let mut reload_error: Option<Report> = None;
+    let mut dirty = true;
 
     loop {
-        terminal.draw(|frame| renderer.render(frame))?;
-        if matches!(event::read()?, Event::Key(_)) {
-            break;
+        if dirty {
+            terminal.draw(|frame| {
+                renderer.render(frame);
+                if let Some(e) = &reload_error {
+                    Renderer::render_error(frame, e);
+                }
+            })?;
+            dirty = false;
+        }
+
+        if event::poll(POLL_RATE)? {
+            match event::read()? {
+                Event::Key(_) => break,
+                // resizes and the like
+                _ => dirty = true,
+            }
+        }
+
+        // drain every pending update, one redraw is enough
+        while updates.try_recv().is_ok() {
+            dirty = true;
+        }
+
+        if watcher.changed() {
+            match load(&layout) {
+                Ok((rt, subroutines, stylesheets)) => {
+                    watcher.also_watch(stylesheets);
+                    // stop the old subroutines before their stores are thrown away
+                    drop(scheduler);
+                    scheduler = Scheduler::start(subroutines, notify.clone());
+                    renderer.swap(rt);
+                    reload_error = None;
+                }
+                Err(e) => reload_error = Some(e),
+            }
+            dirty = true;
         }
     }
 
@@ -1,20 +1,23 @@
 use std::collections::BTreeMap;
 
+use color_eyre::Report;
 use ratatui::{
     Frame,
     buffer::Buffer,
     layout::{Constraint, Direction, Flex, Layout, Rect},
-    widgets::WidgetRef,
+    style::{Color, Style},
+    text::Line,
+    widgets::{Block, Clear, Padding, Paragraph, WidgetRef},
 };
 
 use crate::{
-    backend::{Attribute, ComponentType, RTRef},
-    utils::{flex_from_str, parse_from_attributes},
+    backend::{Attribute, ComponentType, RTRef, diagnostics},
+    utils::{Sides, flex_from_str, parse_or_record},
 };
 
 #[derive(Default)]
 struct LayoutProperties {
-    margin: u16,
+    padding: Padding,
     spacing: u16,
     flex: Flex,
 }
@@ -24,8 +27,8 @@ impl LayoutProperties {
         Self::default()
     }
 
-    fn margin(mut self, margin: Option<u16>) -> Self {
-        self.margin = margin.unwrap_or(0);
+    fn padding(mut self, padding: Option<Sides>) -> Self {
+        self.padding = padding.map(|p| p.0).unwrap_or_default();
         self
     }
 
@@ -39,11 +42,11 @@ impl LayoutProperties {
         self
     }
 
-    fn from_attributes(a: &BTreeMap<String, Attribute>) -> Self {
+    fn from_attributes(tag: &str, a: &BTreeMap<String, Attribute>) -> Self {
         Self::new()
-            .margin(parse_from_attributes(a.get("padding")))
+            .padding(parse_or_record(tag, "padding", a))
             .flex(flex_from_str(a.get("flex")))
-            .spacing(parse_from_attributes(a.get("spacing")))
+            .spacing(parse_or_record(tag, "spacing", a))
     }
 }
 
@@ -56,6 +59,45 @@ impl Renderer {
         Self { tree }
     }
 
+    /// Replace the tree, used when the layout is reloaded
+    pub fn swap(&mut self, tree: Vec<RTRef>) {
+        self.tree = tree;
+    }
+
+    /// Draw an error box over the bottom of the frame
+    pub fn render_error(frame: &mut Frame, error: &Report) {
+        let lines: Vec<Line> = error
+            .chain()
+            .enumerate()
+            .flat_map(|(i, e)| {
+                e.to_string()
+                    .lines()
+                    .map(|l| Line::from(format!("{i}: {l}")))
+                    .collect::<Vec<Line>>()
+            })
+            .collect();
+
+        Self::render_overlay(frame, " Failed to reload layout ", Color::Red, lines);
+    }
+
+    /// Draw a box with `lines` over the bottom of the frame
+    fn render_overlay(frame: &mut Frame, title: &str, color: Color, lines: Vec<Line>) {
+        let area = frame.area();
+        // borders take up two lines
+        let height = (lines.len() as u16 + 2).min(area.height);
+        let area = Rect {
+            y: area.bottom() - height,
+            height,
+            ..area
+        };
+
+        let block = Block::bordered()
+            .title(title)
+            .border_style(Style::new().fg(color));
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+
     pub fn render(&self, frame: &mut Frame) {
         let builder = AreaBuilder::new(frame.area());
 
@@ -64,12 +106,19 @@ impl Renderer {
             ComponentType::Window,
             &self.tree,
             builder,
-            LayoutProperties::new()
+            LayoutProperties::new(),
         );
 
         for (i, t) in self.tree.clone().into_iter().enumerate() {
             Self::recurse_render(t, frame, areas[i]);
         }
+
+        // anything that went wrong while rendering this frame
+        let warnings = diagnostics::take();
+        if !warnings.is_empty() {
+            let lines = warnings.into_iter().map(Line::from).collect();
+            Self::render_overlay(frame, " Warnings ", Color::Yellow, lines);
+        }
     }
 
     fn recurse_render(tree: RTRef, frame: &mut Frame, area_builder: AreaBuilder) {
@@ -77,17 +126,17 @@ impl Renderer {
         let children = lock.children.clone();
         let ctype = lock.ctype;
 
-        area_builder.render_into_area(frame.buffer_mut(), &lock.renderer);
+        area_builder.render_into_area(frame.buffer_mut(), lock.renderer.as_ref());
 
         // a module never have any children
-        if children.len() == 0 || !ctype.is_layout() {
+        if children.is_empty() || !ctype.is_layout() {
             return;
         }
 
         let attributes_read = lock.attributes.read();
 
         // get properties for layout
-        let props = LayoutProperties::from_attributes(&attributes_read);
+        let props = LayoutProperties::from_attributes(&lock.tag, &attributes_read);
 
         let areas: Vec<AreaBuilder> =
             Self::build_children_layout(ctype, &children, area_builder, props);
@@ -99,18 +148,16 @@ impl Renderer {
 
     fn build_children_layout(
         ctype: ComponentType,
-        children: &Vec<RTRef>,
+        children: &[RTRef],
         area_builder: AreaBuilder,
-        layout_properties: LayoutProperties
+        layout_properties: LayoutProperties,
     ) -> Vec<AreaBuilder> {
         let constraints: Vec<Constraint> = children
             .iter()
-            .map(|f| f.borrow().size_constraint.clone())
+            .map(|f| f.borrow().size_constraint)
             .collect();
 
-        let areas = area_builder.layout(ctype.layout_direction(), constraints, layout_properties);
-
-        areas
+        area_builder.layout(ctype.layout_direction(), constraints, layout_properties)
     }
 }
 
@@ -130,18 +177,19 @@ impl AreaBuilder {
         constraints: Vec<Constraint>,
         props: LayoutProperties,
     ) -> Vec<Self> {
+        // ratatui margins are the same on opposite sides, padding may differ on every side
+        let area = Block::new().padding(props.padding).inner(self.area);
         let res = Layout::default()
-            .margin(props.margin)
             .flex(props.flex)
             .spacing(props.spacing)
             .direction(direction)
             .constraints(constraints)
-            .split(self.area);
+            .split(area);
 
         res.iter().map(|a| Self { area: *a }).collect::<Vec<Self>>()
     }
 
-    fn render_into_area(&self, buf: &mut Buffer, widget: &Box<dyn WidgetRef>) {
+    fn render_into_area(&self, buf: &mut Buffer, widget: &dyn WidgetRef) {
         widget.render_ref(self.area, buf);
     }
 }
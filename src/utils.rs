@@ -1,33 +1,76 @@
-use std::{fmt::Debug, str::FromStr};
+use std::{collections::BTreeMap, fmt::Display, str::FromStr, time::Duration};
+
+use color_eyre::eyre::{Error, Result};
 
 use ratatui::{
-    layout::Flex,
-    widgets::{BorderType, Borders},
+    layout::{Alignment, Flex},
+    style::Color,
+    widgets::{BorderType, Borders, Padding},
 };
 
-use crate::backend::Attribute;
+use crate::backend::{Attribute, diagnostics, theme::ThemeColor};
 
 pub fn read_opt_attributes(attr: Option<&Attribute>) -> Option<String> {
     let mut res = None;
 
     if let Some(a) = attr {
-        res = a.read().map(|f| Some(f)).unwrap_or(None);
+        res = a.read().map(Some).unwrap_or_else(|e| {
+            diagnostics::record(e.to_string());
+            None
+        });
     }
     res
 }
 
 pub fn bool_from_optstr(o: Option<String>) -> bool {
-    o.map(|b| b == "true").unwrap_or_default()
+    o.as_deref().and_then(bool_from_str).unwrap_or_default()
+}
+
+/// Like `xs:boolean`, `1` and `0` are allowed too
+pub fn bool_from_str(s: &str) -> Option<bool> {
+    match s.trim() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+pub fn parse_from_attributes<T>(a: Option<&Attribute>) -> Result<Option<T>>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Display,
+{
+    // empty values come from templates whose key is missing, treat them as unset
+    let Some(o) = read_opt_attributes(a).filter(|o| !o.trim().is_empty()) else {
+        return Ok(None);
+    };
+
+    o.parse::<T>()
+        .map(Some)
+        .map_err(|e| Error::msg(format!("Invalid value \"{o}\": {e}")))
 }
 
-pub fn parse_from_attributes<T>(a: Option<&Attribute>) -> Option<T>
+/// `parse_from_attributes` for use while rendering, where values may come from templates
+///
+/// Invalid values are recorded as a diagnostic and treated as if the attribute was missing
+pub fn parse_or_record<T>(tag: &str, name: &str, a: &BTreeMap<String, Attribute>) -> Option<T>
 where
     T: FromStr,
-    <T as FromStr>::Err: Debug
+    <T as FromStr>::Err: Display,
 {
-    let o = read_opt_attributes(a);
+    parse_attr_or_record(tag, name, a.get(name))
+}
 
-    o.map(|f| f.parse::<T>().unwrap())
+/// `parse_or_record` for attributes that are not kept in a map
+pub fn parse_attr_or_record<T>(tag: &str, name: &str, a: Option<&Attribute>) -> Option<T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Display,
+{
+    parse_from_attributes(a).unwrap_or_else(|e| {
+        diagnostics::record(format!("<{tag}> {name}: {e}"));
+        None
+    })
 }
 
 pub fn create_borders(o: Option<&Attribute>) -> Borders {
@@ -87,3 +130,159 @@ pub fn flex_from_str(o: Option<&Attribute>) -> Flex {
         Flex::Start
     }
 }
+
+pub fn alignment_from_str(o: Option<&Attribute>) -> Alignment {
+    alignment_from_name(read_opt_attributes(o).as_deref())
+}
+
+pub fn alignment_from_name(name: Option<&str>) -> Alignment {
+    match name.map(str::trim) {
+        Some("center") => Alignment::Center,
+        Some("right") => Alignment::Right,
+        _ => Alignment::Left,
+    }
+}
+
+/// Parse a duration such as `500ms`, `5s`, `2m` or `1h`
+///
+/// A bare number is read as seconds
+pub fn duration_from_str(s: &str) -> Result<Duration> {
+    let s = s.trim();
+    let split = s
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    let num: f64 = num
+        .parse()
+        .map_err(|_| Error::msg("should be a number followed by ms, s, m or h"))?;
+
+    let secs = match unit.trim() {
+        "ms" => num / 1000.0,
+        "" | "s" => num,
+        "m" => num * 60.0,
+        "h" => num * 3600.0,
+        u => return Err(Error::msg(format!("Unknown duration unit \"{u}\""))),
+    };
+
+    if secs <= 0.0 {
+        return Err(Error::msg("Duration must be larger than zero"));
+    }
+
+    match Duration::try_from_secs_f64(secs) {
+        // below a nanosecond
+        Ok(d) if d.is_zero() => Err(Error::msg("Duration must be at least 1ns")),
+        Ok(d) => Ok(d),
+        Err(_) => Err(Error::msg("Duration is too long")),
+    }
+}
+
+/// Colours a gauge switches to once it is filled to a percentage, e.g. `70=yellow,90=red`
+pub struct Thresholds(Vec<(f64, Color)>);
+
+impl Thresholds {
+    /// Colour of the highest threshold `pct` reaches
+    pub fn color(&self, pct: f64) -> Option<Color> {
+        self.0
+            .iter()
+            .filter(|(t, _)| pct >= *t)
+            .max_by(|a, b| a.0.total_cmp(&b.0))
+            .map(|(_, c)| *c)
+    }
+}
+
+impl FromStr for Thresholds {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.split(',')
+            .map(|t| t.trim())
+            .filter(|t| !t.is_empty())
+            .map(|t| {
+                let (pct, color) = t
+                    .split_once('=')
+                    .ok_or_else(|| Error::msg("should be a list of percent=color"))?;
+                let pct = pct
+                    .trim()
+                    .trim_end_matches('%')
+                    .parse::<f64>()
+                    .map_err(|_| Error::msg(format!("\"{pct}\" is not a number")))?;
+                let color = ThemeColor::from_str(color)
+                    .map(Color::from)
+                    .map_err(|e| Error::msg(format!("\"{}\": {e}", color.trim())))?;
+                Ok((pct, color))
+            })
+            .collect::<Result<Vec<_>>>()
+            .map(Self)
+    }
+}
+
+/// Space on each side, written like CSS: `1`, `1 2` (vertical, horizontal), `1 2 1`
+/// (top, horizontal, bottom) or `1 2 1 2` (top, right, bottom, left)
+pub struct Sides(pub Padding);
+
+impl FromStr for Sides {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let sides = s
+            .split_whitespace()
+            .map(|v| v.parse::<u16>())
+            .collect::<Result<Vec<u16>, _>>()
+            .map_err(|_| Error::msg("should be 1 to 4 whole numbers"))?;
+
+        let padding = match sides[..] {
+            [all] => Padding::uniform(all),
+            [vertical, horizontal] => Padding::symmetric(horizontal, vertical),
+            [top, horizontal, bottom] => Padding::new(horizontal, horizontal, top, bottom),
+            [top, right, bottom, left] => Padding::new(left, right, top, bottom),
+            _ => return Err(Error::msg("should be 1 to 4 whole numbers")),
+        };
+        Ok(Self(padding))
+    }
+}
+
+/// Comma separated list of values, e.g. `green,red`
+pub struct List<T>(pub Vec<T>);
+
+impl<T> FromStr for List<T>
+where
+    T: FromStr,
+    <T as FromStr>::Err: Display,
+{
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        s.split(',')
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .map(|v| {
+                v.parse::<T>()
+                    .map_err(|e| Error::msg(format!("\"{v}\": {e}")))
+            })
+            .collect::<Result<Vec<T>>>()
+            .map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        assert_eq!(
+            duration_from_str("1.5s").unwrap(),
+            Duration::from_millis(1500)
+        );
+        assert_eq!(
+            duration_from_str("250ms").unwrap(),
+            Duration::from_millis(250)
+        );
+        assert_eq!(duration_from_str("2m").unwrap(), Duration::from_secs(120));
+        assert_eq!(duration_from_str("1h").unwrap(), Duration::from_secs(3600));
+
+        for bad in ["0", "0.0000000001", "99999999999999999999999h", "5d", "s"] {
+            assert!(duration_from_str(bad).is_err(), "{bad}");
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use std::{
+    fs,
+    path::PathBuf,
+    time::{Duration, Instant, SystemTime},
+};
+
+/// How often the watched files are checked for changes
+const CHECK_RATE: Duration = Duration::from_millis(500);
+
+/// Polls the modification time of the layout and the files it uses
+pub struct Watcher {
+    /// The layout comes first
+    files: Vec<(PathBuf, Option<SystemTime>)>,
+    last_check: Instant,
+}
+
+impl Watcher {
+    pub fn new<P: Into<PathBuf>>(p: P) -> Self {
+        let path = p.into();
+        let modified = Self::modified(&path);
+
+        Self {
+            files: vec![(path, modified)],
+            last_check: Instant::now(),
+        }
+    }
+
+    /// Watch `paths` next to the layout, replacing the ones watched before
+    pub fn also_watch(&mut self, paths: Vec<PathBuf>) {
+        self.files.truncate(1);
+        self.files.extend(paths.into_iter().map(|p| {
+            let modified = Self::modified(&p);
+            (p, modified)
+        }));
+    }
+
+    /// Returns true once for every change to the files, checks at most once every `CHECK_RATE`
+    pub fn changed(&mut self) -> bool {
+        if self.last_check.elapsed() < CHECK_RATE {
+            return false;
+        }
+        self.last_check = Instant::now();
+
+        let mut changed = false;
+        for (path, last) in &mut self.files {
+            // editors may remove the file while saving, wait for it to come back
+            let Some(modified) = Self::modified(path) else {
+                continue;
+            };
+
+            if *last != Some(modified) {
+                *last = Some(modified);
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    fn modified(path: &PathBuf) -> Option<SystemTime> {
+        fs::metadata(path).and_then(|m| m.modified()).ok()
+    }
+}